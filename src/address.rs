@@ -0,0 +1,184 @@
+use crate::config::configured_bech32_prefix;
+use anyhow::{Error, Result};
+use bech32::{encode, primitives::decode::CheckedHrpstring, Bech32, Hrp};
+use cosmwasm_std::{Addr, CanonicalAddr};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
+use std::ops::Deref;
+
+/// A bech32 address validated against the configured prefix when it is parsed,
+/// so typos fail before anything is sent to the chain.
+/// Deserializing only checks the bech32 encoding, files holding addresses are checked
+/// against the network prefix with [`Address::validate`] when they are loaded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(into = "String")]
+pub struct Address(String);
+
+impl Address {
+    pub fn parse(address: &str, bech32_prefix: &str) -> Result<Self, Error> {
+        addr_canonicalize(address, bech32_prefix)
+            .map_err(|e| Error::msg(format!("Invalid address {}: {}", address, e)))?;
+        Ok(Address(address.to_string()))
+    }
+
+    /// Checks an address that was not parsed with a prefix, e.g. one read from a file
+    pub fn validate(&self, bech32_prefix: &str) -> Result<(), Error> {
+        Address::parse(&self.0, bech32_prefix).map(|_| ())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for Address {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Address {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let address = String::deserialize(deserializer)?;
+        CheckedHrpstring::new::<Bech32>(&address).map_err(|_| {
+            serde::de::Error::custom(format!(
+                "Invalid address {}: Error decoding bech32",
+                address
+            ))
+        })?;
+        Ok(Address(address))
+    }
+}
+
+impl From<Address> for String {
+    fn from(address: Address) -> Self {
+        address.0
+    }
+}
+
+/// Parses addresses of contracts the tool derived itself, e.g. to record them in a manifest
+pub fn parse_addresses(addresses: &[String], bech32_prefix: &str) -> Result<Vec<Address>, Error> {
+    addresses
+        .iter()
+        .map(|address| Address::parse(address, bech32_prefix))
+        .collect()
+}
+
+/// The plain strings of addresses, for the message builders that take them
+pub fn address_strings(addresses: &[Address]) -> Vec<String> {
+    addresses
+        .iter()
+        .map(|address| address.to_string())
+        .collect()
+}
+
+/// Parses a CLI address argument with the configured bech32 prefix
+pub fn parse_address(s: &str) -> Result<Address, String> {
+    Address::parse(s, &configured_bech32_prefix()).map_err(|e| e.to_string())
+}
+
+/// Converts a Bech32 address to canonical format.
+pub fn addr_canonicalize(input: &str, bech32_prefix: &str) -> Result<CanonicalAddr, Error> {
+    let hrp_str =
+        CheckedHrpstring::new::<Bech32>(input).map_err(|_| Error::msg("Error decoding bech32"))?;
+
+    // Ensure the Bech32 prefix is correct
+    if !hrp_str
+        .hrp()
+        .as_bytes()
+        .eq_ignore_ascii_case(bech32_prefix.as_bytes())
+    {
+        return Err(Error::msg(format!(
+            "Wrong bech32 prefix, expected {}",
+            bech32_prefix
+        )));
+    }
+
+    // Collect bytes from the decoded Bech32 address
+    let bytes: Vec<u8> = hrp_str.byte_iter().collect();
+
+    // Validate the address length
+    validate_length(&bytes)?;
+
+    // Return the address as a CanonicalAddr
+    Ok(bytes.into())
+}
+
+/// Converts a canonical address back to a human-readable address.
+pub fn addr_humanize(canonical: &CanonicalAddr, bech32_prefix: &str) -> Result<Addr, Error> {
+    // Validate the canonical address length
+    validate_length(canonical.as_ref())?;
+
+    // Parse the Bech32 prefix
+    let prefix = Hrp::parse(bech32_prefix).map_err(|_| Error::msg("Invalid bech32 prefix"))?;
+
+    // Encode the canonical address back to Bech32 format
+    encode::<Bech32>(prefix, canonical.as_slice())
+        .map(Addr::unchecked)
+        .map_err(|_| Error::msg("Bech32 encoding error"))
+}
+
+/// Basic validation for the number of bytes in a canonical address
+fn validate_length(bytes: &[u8]) -> Result<()> {
+    match bytes.len() {
+        1..=255 => Ok(()),
+        _ => Err(Error::msg("Invalid canonical address length")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDRESS: &str = "neutron1qqqsyqcyq5rqwzqfpg9scrgwpugpzysn8fcct7";
+
+    #[test]
+    fn parse_checks_the_prefix() {
+        assert_eq!(
+            Address::parse(ADDRESS, "neutron").unwrap().as_str(),
+            ADDRESS
+        );
+        assert!(Address::parse(ADDRESS, "cosmos").is_err());
+        assert!(
+            Address::parse("cosmos1qqqsyqcyq5rqwzqfpg9scrgwpugpzysnrk363e", "neutron").is_err()
+        );
+    }
+
+    #[test]
+    fn parse_rejects_bad_checksums() {
+        assert!(
+            Address::parse("neutron1qqqsyqcyq5rqwzqfpg9scrgwpugpzysn8fcct8", "neutron").is_err()
+        );
+        assert!(Address::parse("neutron", "neutron").is_err());
+        assert!(Address::parse("", "neutron").is_err());
+    }
+
+    #[test]
+    fn deserialized_addresses_are_validated_on_load() {
+        let address: Address = serde_json::from_str(&format!("\"{}\"", ADDRESS)).unwrap();
+        assert!(address.validate("neutron").is_ok());
+        assert!(address.validate("cosmos").is_err());
+        assert!(serde_json::from_str::<Address>("\"neutron1invalid\"").is_err());
+    }
+
+    #[test]
+    fn canonical_round_trip() {
+        let canonical = addr_canonicalize(ADDRESS, "neutron").unwrap();
+        assert_eq!(
+            canonical.as_slice(),
+            (0u8..20).collect::<Vec<_>>().as_slice()
+        );
+        assert_eq!(
+            addr_humanize(&canonical, "neutron").unwrap().as_str(),
+            ADDRESS
+        );
+    }
+}
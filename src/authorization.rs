@@ -1,10 +1,12 @@
 use anyhow::{Error, Result};
 use cosmwasm_std::{to_json_vec, Binary};
 use serde::Deserialize;
-use valence_astroport_lper;
 use valence_authorization_utils::msg::ProcessorMessage;
 use valence_library_utils::msg::ExecuteMsg;
-use valence_splitter_library;
+
+pub const SPLIT: &str = "split";
+pub const PROVIDE_DOUBLE_SIDED_LIQUIDITY: &str = "provide_double_sided_liquidity";
+pub const WITHDRAW_LIQUIDITY: &str = "withdraw_liquidity";
 
 #[derive(Debug, Deserialize)]
 pub struct AuthorizationsResponse {
@@ -14,9 +16,28 @@ pub struct AuthorizationsResponse {
 #[derive(Debug, Deserialize)]
 pub struct Authorization {
     pub label: String,
+    #[serde(default)]
+    pub mode: serde_json::Value,
+    #[serde(default)]
+    pub not_before: serde_json::Value,
+    #[serde(default)]
+    pub expiration: serde_json::Value,
+    #[serde(default)]
+    pub max_concurrent_executions: Option<u64>,
+    #[serde(default)]
+    pub priority: serde_json::Value,
+    #[serde(default)]
+    pub state: serde_json::Value,
     pub subroutine: Subroutine,
 }
 
+impl Authorization {
+    /// Disabled authorizations keep their label but can't be executed anymore
+    pub fn is_enabled(&self) -> bool {
+        self.state.as_str() != Some("disabled")
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct Subroutine {
     pub atomic: AtomicSubroutine,
@@ -29,59 +50,78 @@ pub struct AtomicSubroutine {
 
 #[derive(Debug, Deserialize)]
 pub struct Function {
+    pub contract_address: serde_json::Value,
     pub message_details: MessageDetails,
 }
 
+impl Function {
+    /// Returns the library address targeted by the function, if it is given as an address
+    pub fn contract_address(&self) -> Option<String> {
+        match &self.contract_address {
+            serde_json::Value::String(address) => Some(address.clone()),
+            serde_json::Value::Object(map) => {
+                map.values().find_map(|v| v.as_str()).map(String::from)
+            }
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct MessageDetails {
     pub message: serde_json::Value,
 }
 
-fn get_functions_identifiers(authorization: &Authorization) -> Result<Vec<String>, Error> {
-    let mut function_identifiers = Vec::new();
-
-    for function in &authorization.subroutine.atomic.functions {
-        if let Some(name) = function
-            .message_details
-            .message
-            .get("name")
-            .and_then(|v| v.as_str())
+/// Returns the library function (split, provide_double_sided_liquidity or withdraw_liquidity)
+/// that the authorization function is restricted to
+pub fn get_function_identifier(function: &Function) -> Option<String> {
+    let name = function
+        .message_details
+        .message
+        .get("name")
+        .and_then(|v| v.as_str())?;
+    if name != "process_function" {
+        return None;
+    }
+
+    let params_restrictions: Vec<serde_json::Value> = function
+        .message_details
+        .message
+        .get("params_restrictions")
+        .and_then(|v| v.as_array())
+        .unwrap_or(&Vec::new())
+        .to_vec();
+
+    for restriction in params_restrictions {
+        if let Some(must_be_included) = restriction
+            .get("must_be_included")
+            .and_then(|v| v.as_array())
         {
-            if name == "process_function" {
-                let params_restrictions: Vec<serde_json::Value> = function
-                    .message_details
-                    .message
-                    .get("params_restrictions")
-                    .and_then(|v| v.as_array())
-                    .unwrap_or(&Vec::new())
-                    .to_vec();
-
-                for restriction in params_restrictions {
-                    if let Some(must_be_included) = restriction
-                        .get("must_be_included")
-                        .and_then(|v| v.as_array())
-                    {
-                        for param in must_be_included {
-                            if let Some(param_str) = param.as_str() {
-                                // Push the relevant function identifiers to the result list
-                                match param_str {
-                                    "split" => function_identifiers.push("split".to_string()),
-                                    "provide_double_sided_liquidity" => function_identifiers
-                                        .push("provide_double_sided_liquidity".to_string()),
-                                    "withdraw_liquidity" => {
-                                        function_identifiers.push("withdraw_liquidity".to_string())
-                                    }
-                                    _ => {}
-                                }
-                            }
+            for param in must_be_included {
+                if let Some(param_str) = param.as_str() {
+                    // Return the relevant function identifier
+                    match param_str {
+                        SPLIT | PROVIDE_DOUBLE_SIDED_LIQUIDITY | WITHDRAW_LIQUIDITY => {
+                            return Some(param_str.to_string())
                         }
+                        _ => {}
                     }
                 }
             }
         }
     }
 
-    Ok(function_identifiers)
+    None
+}
+
+fn get_functions_identifiers(authorization: &Authorization) -> Result<Vec<String>, Error> {
+    Ok(authorization
+        .subroutine
+        .atomic
+        .functions
+        .iter()
+        .filter_map(get_function_identifier)
+        .collect())
 }
 
 pub fn create_execute_messages_for_authorization(
@@ -94,7 +134,7 @@ pub fn create_execute_messages_for_authorization(
     // Create message for each identifier
     for identifier in function_identifiers {
         match identifier.as_str() {
-            "split" => {
+            SPLIT => {
                 // Create message for "split"
                 let split_bin = Binary::from(
                     to_json_vec(&ExecuteMsg::<_, ()>::ProcessFunction(
@@ -105,7 +145,7 @@ pub fn create_execute_messages_for_authorization(
                 let split_msg = ProcessorMessage::CosmwasmExecuteMsg { msg: split_bin };
                 messages.push(split_msg);
             }
-            "provide_double_sided_liquidity" => {
+            PROVIDE_DOUBLE_SIDED_LIQUIDITY => {
                 // Create message for "provide_double_sided_liquidity"
                 let astro_lper_bin = Binary::from(
                     to_json_vec(&ExecuteMsg::<_, ()>::ProcessFunction(
@@ -120,7 +160,7 @@ pub fn create_execute_messages_for_authorization(
                 };
                 messages.push(astro_lper_msg);
             }
-            "withdraw_liquidity" => {
+            WITHDRAW_LIQUIDITY => {
                 // Create message for "withdraw_liquidity"
                 let withdraw_bin = Binary::from(
                     to_json_vec(&ExecuteMsg::<_, ()>::ProcessFunction(
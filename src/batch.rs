@@ -0,0 +1,157 @@
+use crate::config::Config;
+use crate::fees::check_fee_allowance;
+use crate::funds::format_coins;
+use crate::plan::PlannedContract;
+use crate::wasm::{
+    broadcast_msgs, find_event_attributes, generate_bank_send_msg, generate_wasm_execute_msg,
+    generate_wasm_instantiate2_msg,
+};
+use anyhow::{anyhow, Result};
+use cosmwasm_std::Coin;
+use serde::Serialize;
+use serde_json::Value;
+
+struct BatchedMsg {
+    msg: Value,
+    gas: u64,
+    // address the message is expected to instantiate a contract at
+    expected_address: Option<String>,
+}
+
+/// Collects messages and broadcasts them in as few transactions as the
+/// configured message count and gas limits allow
+pub struct TxBatch<'a> {
+    config: &'a Config,
+    msgs: Vec<BatchedMsg>,
+}
+
+impl<'a> TxBatch<'a> {
+    pub fn new(config: &'a Config) -> Self {
+        TxBatch {
+            config,
+            msgs: Vec::new(),
+        }
+    }
+
+    /// Adds an instantiate2 message for the planned contract and returns its predicted address
+    pub fn instantiate2<T: Serialize>(
+        &mut self,
+        contract: &PlannedContract,
+        msg: &T,
+    ) -> Result<String> {
+        let (msg, gas) = generate_wasm_instantiate2_msg(
+            contract.code_id,
+            &serde_json::to_string(msg)?,
+            self.config,
+            &contract.label,
+            &contract.salt,
+        )?;
+        self.msgs.push(BatchedMsg {
+            msg,
+            gas,
+            expected_address: Some(contract.address.clone()),
+        });
+
+        Ok(contract.address.clone())
+    }
+
+    pub fn execute<T: Serialize>(&mut self, contract_address: &str, msg: &T) -> Result<()> {
+        let (msg, gas) =
+            generate_wasm_execute_msg(contract_address, &serde_json::to_string(msg)?, self.config)?;
+        self.msgs.push(BatchedMsg {
+            msg,
+            gas,
+            expected_address: None,
+        });
+
+        Ok(())
+    }
+
+    /// Adds a bank send of the coins from the operator
+    pub fn send(&mut self, to_address: &str, amount: &[Coin]) -> Result<()> {
+        let (msg, gas) = generate_bank_send_msg(to_address, &format_coins(amount), self.config)?;
+        self.msgs.push(BatchedMsg {
+            msg,
+            gas,
+            expected_address: None,
+        });
+
+        Ok(())
+    }
+
+    /// Broadcasts the collected messages, waiting for every transaction to be included
+    /// so the messages added afterwards can depend on them
+    pub fn broadcast(&mut self) -> Result<()> {
+        let msgs = std::mem::take(&mut self.msgs);
+        let mut remaining = msgs.as_slice();
+
+        // Make sure the fee grant covers all the transactions before sending the first one
+        let total_gas: u64 = msgs.iter().map(|batched| batched.gas).sum();
+        let msg_types: Vec<String> = msgs
+            .iter()
+            .filter_map(|batched| batched.msg["@type"].as_str().map(String::from))
+            .collect();
+        check_fee_allowance(self.config, total_gas, &msg_types)?;
+
+        while !remaining.is_empty() {
+            let tx_msgs = self.next_tx(remaining);
+            remaining = &remaining[tx_msgs.len()..];
+
+            // Gas was estimated per message, each including the transaction overhead, so the sum is an upper bound
+            let gas: u64 = tx_msgs.iter().map(|batched| batched.gas).sum();
+            let msgs: Vec<Value> = tx_msgs.iter().map(|batched| batched.msg.clone()).collect();
+            println!(
+                "Broadcasting {} message(s) with {} gas ...",
+                msgs.len(),
+                gas
+            );
+            let tx_data = broadcast_msgs(&msgs, gas, self.config)?;
+
+            let instantiated = find_event_attributes(&tx_data, "_contract_address");
+            for expected_address in tx_msgs
+                .iter()
+                .filter_map(|batched| batched.expected_address.as_ref())
+            {
+                if !instantiated.contains(expected_address) {
+                    return Err(anyhow!(
+                        "Contract was not instantiated at the predicted address {}",
+                        expected_address
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Number of transactions and total gas the collected messages would be broadcast with
+    pub fn estimate(&self) -> (usize, u64) {
+        let mut remaining = self.msgs.as_slice();
+        let mut txs = 0;
+        while !remaining.is_empty() {
+            remaining = &remaining[self.next_tx(remaining).len()..];
+            txs += 1;
+        }
+
+        (txs, self.msgs.iter().map(|batched| batched.gas).sum())
+    }
+
+    pub fn msgs_count(&self) -> usize {
+        self.msgs.len()
+    }
+
+    /// Takes the longest prefix of the messages within the limits, at least one message
+    fn next_tx<'m>(&self, msgs: &'m [BatchedMsg]) -> &'m [BatchedMsg] {
+        let mut gas = 0;
+        let mut count = 0;
+        for batched in msgs.iter().take(self.config.max_msgs_per_tx) {
+            if count > 0 && gas + batched.gas > self.config.max_gas_per_tx {
+                break;
+            }
+            gas += batched.gas;
+            count += 1;
+        }
+
+        &msgs[..count.max(1)]
+    }
+}
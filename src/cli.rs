@@ -1,6 +1,13 @@
+use crate::address::{parse_address, Address};
+use crate::program_spec::AuthorizationModeSpec;
+use crate::scheduler::parse_timestamp;
 use clap::{Parser, Subcommand, ValueEnum};
-use serde::Deserialize;
+use cosmwasm_std::{Decimal, Timestamp, Uint128};
+use cw_utils::Expiration;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 use valence_astroport_utils::{astroport_cw20_lp_token, astroport_native_lp_token, PoolType};
+use valence_authorization_utils::{authorization::Priority, msg::Mint};
 
 #[derive(Parser)]
 #[command(name = "liquidity-deployment-tool")]
@@ -8,6 +15,13 @@ use valence_astroport_utils::{astroport_cw20_lp_token, astroport_native_lp_token
 pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Directory the offline signer writes unsigned transactions to and reads the signed ones from, required with LD_TOOL_SIGNER=offline"
+    )]
+    pub offline_dir: Option<PathBuf>,
 }
 
 #[derive(Subcommand)]
@@ -20,47 +34,710 @@ pub enum Commands {
         )]
         label_prefix: String,
 
-        #[arg(long, help = "Pool information in the format 'address,amount_a,amount_b,denom_a,denom_b'", value_parser = parse_pool)]
+        #[arg(long, help = "Pool information in the format 'address,amount_a,amount_b,denom_a,denom_b,pool_type[,name]'", value_parser = parse_pool)]
         pools: Vec<PoolInfo>,
+
+        #[arg(
+            long,
+            help = "Also create <prefix>_<pool>_deploy and <prefix>_<pool>_withdraw authorizations for every pool. A per-pool deploy only provides liquidity, the program-wide deploy splits the funds to the pools"
+        )]
+        per_pool_authorizations: bool,
+
+        #[arg(
+            long,
+            help = "Path to a JSON program spec with authorization modes, time windows and priorities"
+        )]
+        program_spec: Option<String>,
+
+        #[arg(
+            long,
+            help = "Salt mixed into the contract addresses, random if not set. Reuse it to reproduce a deployment's addresses"
+        )]
+        salt: Option<String>,
+
+        #[arg(
+            long,
+            help = "Print the predicted address of every contract without deploying"
+        )]
+        dry_run: bool,
+
+        #[arg(
+            long,
+            conflicts_with = "dry_run",
+            help = "Simulate the deployment and print its gas and fees without deploying"
+        )]
+        estimate: bool,
     },
 
     /// Execute a program using the authorization contract address and the action (deploy or withdraw)
     ExecuteProgram {
-        #[arg(long, help = "Authorization contract address")]
-        auth_contract_address: String,
+        #[arg(long, help = "Authorization contract address", value_parser = parse_address)]
+        auth_contract_address: Address,
 
         #[arg(value_enum, long, help = "Action to perform (deploy or withdraw)")]
         action: ProgramAction,
+
+        #[arg(
+            long,
+            help = "Pool name, or address of a pool deployed without a name, to execute only that pool's authorization. A per-pool deploy doesn't split, the funds must have been split by the program-wide deploy"
+        )]
+        pool: Option<String>,
+
+        #[arg(
+            long,
+            help = "Tick the processor until the execution completes and exit non-zero on failure"
+        )]
+        wait: bool,
+
+        #[arg(
+            long,
+            default_value_t = 50,
+            help = "Maximum number of ticks while waiting for the execution"
+        )]
+        max_iterations: u32,
     },
 
     /// Tick the processor contract with the given address
     TickProcessor {
-        #[arg(long, help = "Processor contract address")]
-        processor_contract_address: String,
+        #[arg(long, help = "Processor contract address", value_parser = parse_address)]
+        processor_contract_address: Address,
+
+        #[arg(long, help = "Keep ticking until the processor queue is drained")]
+        until_empty: bool,
+
+        #[arg(
+            long,
+            default_value_t = 50,
+            help = "Maximum number of ticks when ticking until the queue is empty"
+        )]
+        max_iterations: u32,
+    },
+
+    /// Watch processors and tick them whenever their queue is not empty
+    TickDaemon {
+        #[arg(
+            long = "processor-contract-address",
+            required = true,
+            help = "Processor contract address (can be repeated)",
+            value_parser = parse_address
+        )]
+        processor_contract_addresses: Vec<Address>,
+
+        #[arg(long, default_value_t = 6, help = "Seconds between queue checks")]
+        poll_interval_secs: u64,
+
+        #[arg(
+            long,
+            default_value_t = 60,
+            help = "Seconds to wait after a failed tick before ticking the processor again"
+        )]
+        failure_cooldown_secs: u64,
+
+        #[arg(
+            long,
+            default_value_t = 60,
+            help = "Seconds between ticks of a batch waiting for a retry"
+        )]
+        retry_cooldown_secs: u64,
+
+        #[arg(long, help = "Total gas the daemon may spend on ticks")]
+        gas_budget: Option<u64>,
+
+        #[arg(
+            long,
+            help = "Address to serve Prometheus metrics on, e.g. 0.0.0.0:9464"
+        )]
+        metrics_address: Option<String>,
+    },
+
+    /// Schedule a deploy or withdraw at a given time or on a cron expression
+    ScheduleAdd {
+        #[arg(long, default_value = "schedule.json", help = "Schedule state file")]
+        schedule_file: String,
+
+        #[arg(long, help = "Authorization contract address", value_parser = parse_address)]
+        auth_contract_address: Address,
+
+        #[arg(value_enum, long, help = "Action to perform (deploy or withdraw)")]
+        action: ProgramAction,
+
+        #[arg(
+            long,
+            help = "Pool name, or address of a pool deployed without a name, to execute only that pool's authorization. A per-pool deploy doesn't split, the funds must have been split by the program-wide deploy"
+        )]
+        pool: Option<String>,
+
+        #[arg(long, conflicts_with = "cron", required_unless_present = "cron", help = "Run once at an RFC 3339 date or Unix timestamp", value_parser = parse_timestamp)]
+        at: Option<i64>,
+
+        #[arg(
+            long,
+            help = "Run on a cron expression with seconds, e.g. '0 0 12 * * Fri *'"
+        )]
+        cron: Option<String>,
+
+        #[arg(long, help = "Tick the processor until the execution completes")]
+        wait: bool,
+
+        #[arg(
+            long,
+            requires = "at",
+            help = "Also set the authorizations' not_before/expiration window around the scheduled time"
+        )]
+        sync_authorization_window: bool,
+
+        #[arg(
+            long,
+            default_value_t = 3600,
+            help = "Length of the authorization window in seconds"
+        )]
+        window_secs: u64,
+
+        #[arg(
+            long,
+            help = "Print the authorization window change as a DAO proposal payload instead of executing. The scheduler waits for the window to be set on chain before running"
+        )]
+        proposal: bool,
+    },
+
+    /// List scheduled executions
+    ScheduleList {
+        #[arg(long, default_value = "schedule.json", help = "Schedule state file")]
+        schedule_file: String,
+    },
+
+    /// Remove a scheduled execution
+    ScheduleRemove {
+        #[arg(long, default_value = "schedule.json", help = "Schedule state file")]
+        schedule_file: String,
+
+        #[arg(long, help = "Id of the scheduled execution")]
+        id: u64,
+    },
+
+    /// Run scheduled executions when they are due
+    ScheduleRun {
+        #[arg(long, default_value = "schedule.json", help = "Schedule state file")]
+        schedule_file: String,
+
+        #[arg(long, default_value_t = 30, help = "Seconds between schedule checks")]
+        poll_interval_secs: u64,
+
+        #[arg(
+            long,
+            default_value_t = 50,
+            help = "Maximum number of ticks while waiting for an execution"
+        )]
+        max_iterations: u32,
+
+        #[arg(
+            long,
+            default_value_t = 3,
+            help = "Times a failed one-off execution is retried, one poll interval apart"
+        )]
+        max_retries: u32,
+    },
+
+    /// List the message batches queued on the processor, by priority
+    ProcessorQueue {
+        #[arg(long, help = "Processor contract address", value_parser = parse_address)]
+        processor_contract_address: Address,
+    },
+
+    /// Add new authorizations to an existing program
+    AddAuthorizations {
+        #[arg(long, help = "Authorization contract address", value_parser = parse_address)]
+        auth_contract_address: Address,
+
+        #[arg(
+            long,
+            help = "Path to a JSON file with the list of authorizations to create"
+        )]
+        authorizations_file: String,
+
+        #[arg(long, help = "Print a DAO proposal payload instead of executing")]
+        proposal: bool,
+    },
+
+    /// Disable an authorization
+    DisableAuthorization {
+        #[arg(long, help = "Authorization contract address", value_parser = parse_address)]
+        auth_contract_address: Address,
+
+        #[arg(long, help = "Label of the authorization")]
+        label: String,
+
+        #[arg(long, help = "Print a DAO proposal payload instead of executing")]
+        proposal: bool,
+    },
+
+    /// Enable a previously disabled authorization
+    EnableAuthorization {
+        #[arg(long, help = "Authorization contract address", value_parser = parse_address)]
+        auth_contract_address: Address,
+
+        #[arg(long, help = "Label of the authorization")]
+        label: String,
+
+        #[arg(long, help = "Print a DAO proposal payload instead of executing")]
+        proposal: bool,
+    },
+
+    /// Modify the time window, concurrency, priority, permissions or mode of an authorization
+    ModifyAuthorization {
+        #[arg(long, help = "Authorization contract address", value_parser = parse_address)]
+        auth_contract_address: Address,
+
+        #[arg(long, help = "Label of the authorization")]
+        label: String,
+
+        #[arg(long, help = "Not before in the format 'time:<unix_seconds>', 'height:<block>' or 'never'", value_parser = parse_expiration)]
+        not_before: Option<Expiration>,
+
+        #[arg(long, help = "Expiration in the format 'time:<unix_seconds>', 'height:<block>' or 'never'", value_parser = parse_expiration)]
+        expiration: Option<Expiration>,
+
+        #[arg(long, help = "Maximum number of concurrent executions")]
+        max_concurrent_executions: Option<u64>,
+
+        #[arg(value_enum, long, help = "Priority of the authorization")]
+        priority: Option<AuthorizationPriority>,
+
+        #[arg(long, help = "Mint permission tokens of a permissioned authorization in the format 'address[:amount]'", value_parser = parse_mint)]
+        mint: Vec<Mint>,
+
+        #[arg(
+            value_enum,
+            long,
+            help = "New mode. The mode can't be modified in place, so the authorization is disabled and recreated with the new mode as '<label>.<revision>'"
+        )]
+        mode: Option<AuthorizationModeArg>,
+
+        #[arg(long, help = "Address allowed to execute the authorization with --mode permissioned", value_parser = parse_address)]
+        mode_address: Vec<Address>,
+
+        #[arg(long, help = "Call limit of every address with --mode permissioned")]
+        call_limit: Option<u128>,
+
+        #[arg(long, help = "Print a DAO proposal payload instead of executing")]
+        proposal: bool,
+    },
+
+    /// Report authorizations, processor queue, ownership, approved libraries, balances and LP positions of a program
+    Status {
+        #[arg(
+            long,
+            visible_alias = "auth-contract",
+            help = "Authorization contract address",
+            value_parser = parse_address
+        )]
+        auth_contract_address: Address,
+
+        #[arg(
+            long,
+            help = "Program manifest saved by create-program (needed to include withdrawal accounts)"
+        )]
+        manifest: Option<String>,
+    },
+
+    /// Report pending and accepted ownership of the program contracts and print an accept-ownership proposal
+    VerifyOwnership {
+        #[arg(
+            long,
+            visible_alias = "auth-contract",
+            help = "Authorization contract address",
+            value_parser = parse_address
+        )]
+        auth_contract_address: Address,
+
+        #[arg(
+            long,
+            help = "Program manifest saved by create-program (needed to include withdrawal accounts)"
+        )]
+        manifest: Option<String>,
+
+        #[arg(
+            long,
+            help = "Transfer ownership again with the operator key where the transfer expired or was never made"
+        )]
+        reissue_expired: bool,
+    },
+
+    /// Send the pool amounts of a program, summed per denom, to its input account
+    FundProgram {
+        #[arg(long, help = "Program manifest saved by create-program")]
+        manifest: String,
+
+        #[arg(
+            long,
+            help = "Print a DAO proposal paying from the treasury instead of sending from the operator"
+        )]
+        proposal: bool,
+
+        #[arg(
+            long,
+            requires = "proposal",
+            help = "DAO treasury the proposal pays from (defaults to the DAO committee address)",
+            value_parser = parse_address
+        )]
+        treasury_address: Option<Address>,
+    },
+
+    /// List the balances of the program accounts and send everything they hold to an address
+    Sweep {
+        #[arg(
+            long,
+            visible_alias = "auth-contract",
+            help = "Authorization contract address",
+            value_parser = parse_address
+        )]
+        auth_contract_address: Address,
+
+        #[arg(
+            long,
+            help = "Program manifest saved by create-program (needed to include withdrawal accounts)"
+        )]
+        manifest: Option<String>,
+
+        #[arg(long, help = "Address receiving the swept funds", value_parser = parse_address)]
+        recipient: Address,
+
+        #[arg(
+            long,
+            help = "Execute with the operator key, only possible before ownership is transferred to the DAO"
+        )]
+        execute: bool,
+    },
+
+    /// Add a pool to a deployed program: creates its accounts and libraries and proposes the new
+    /// splits and authorizations to the DAO committee
+    AddPool {
+        #[arg(long, help = "Program manifest saved by create-program")]
+        manifest: String,
+
+        #[arg(long, help = "Pool information in the format 'address,amount_a,amount_b,denom_a,denom_b,pool_type[,name]'", value_parser = parse_pool)]
+        pool: PoolInfo,
+
+        #[arg(
+            long,
+            help = "Also create <prefix>_<pool>_deploy and <prefix>_<pool>_withdraw authorizations for the pool. The per-pool deploy only provides liquidity, the program-wide deploy splits the funds to the pools"
+        )]
+        per_pool_authorizations: bool,
+
+        #[arg(
+            long,
+            help = "Path to a JSON program spec with authorization modes, time windows and priorities"
+        )]
+        program_spec: Option<String>,
+
+        #[arg(
+            long,
+            help = "Execute the splitter, authorization and ownership updates with the operator key instead of printing a DAO proposal payload, only possible when the operator is the DAO committee"
+        )]
+        execute: bool,
+    },
+
+    /// Withdraw the liquidity of a pool and propose removing it from the splits and authorizations of the program
+    RemovePool {
+        #[arg(long, help = "Program manifest saved by create-program")]
+        manifest: String,
+
+        #[arg(long, help = "Pool name or address")]
+        pool: String,
+
+        #[arg(
+            long,
+            help = "Don't withdraw the pool liquidity first, refused while the pool accounts hold funds or LP tokens. Pools without a per-pool withdraw authorization are withdrawn with the program-wide withdraw, which withdraws every pool"
+        )]
+        skip_withdraw: bool,
+
+        #[arg(
+            long,
+            default_value_t = 50,
+            help = "Maximum number of ticks while waiting for the withdrawal"
+        )]
+        max_iterations: u32,
+
+        #[arg(
+            long,
+            help = "Path to a JSON program spec with authorization modes, time windows and priorities"
+        )]
+        program_spec: Option<String>,
+
+        #[arg(
+            long,
+            help = "Execute the splitter, authorization and ownership updates with the operator key instead of printing a DAO proposal payload, only possible when the operator is the DAO committee"
+        )]
+        execute: bool,
+    },
+
+    /// Reconstruct the processor, libraries, accounts and pools of a program from its authorization contract
+    Inspect {
+        #[arg(
+            long,
+            visible_alias = "auth-contract",
+            help = "Authorization contract address",
+            value_parser = parse_address
+        )]
+        auth_contract_address: Address,
+
+        #[arg(
+            long,
+            help = "Program manifest saved by create-program (needed to include withdrawal accounts)"
+        )]
+        manifest: Option<String>,
+
+        #[arg(value_enum, long, default_value = "json", help = "Output format")]
+        format: TopologyFormat,
+
+        #[arg(long, help = "File to write the topology to instead of printing it")]
+        output: Option<String>,
+    },
+
+    /// Draw the flow of funds of a program, from a manifest or from the deployed contracts
+    Diagram {
+        #[arg(
+            long,
+            visible_alias = "auth-contract",
+            required_unless_present = "manifest",
+            help = "Authorization contract address, to draw the deployed program",
+            value_parser = parse_address
+        )]
+        auth_contract_address: Option<Address>,
+
+        #[arg(
+            long,
+            help = "Program manifest saved by create-program, drawn without querying the chain unless --auth-contract-address is set"
+        )]
+        manifest: Option<String>,
+
+        #[arg(value_enum, long, default_value = "mermaid", help = "Output format")]
+        format: DiagramFormat,
+
+        #[arg(long, help = "File to write the diagram to instead of printing it")]
+        output: Option<String>,
+    },
+
+    /// Write the markdown DAO proposal accepting ownership of a program created by create-program
+    Proposal {
+        #[arg(long, help = "Program manifest saved by create-program")]
+        manifest: String,
+
+        #[arg(long, help = "File to write the proposal to instead of printing it")]
+        output: Option<String>,
+    },
+
+    /// Update the config of a deployed splitter, Astroport LPer or Astroport withdrawer library
+    UpdateConfig {
+        #[command(subcommand)]
+        library: LibraryConfigCommand,
+
+        #[arg(
+            long,
+            global = true,
+            help = "Execute with the operator key instead of printing a DAO proposal payload"
+        )]
+        execute: bool,
     },
 }
 
-#[derive(ValueEnum, Debug, Clone)]
+impl Commands {
+    /// Whether the command signs transactions or predicts contract addresses, and so
+    /// depends on the configured network and operator key
+    pub fn uses_operator(&self) -> bool {
+        match self {
+            Commands::Sweep { execute, .. } => *execute,
+            Commands::VerifyOwnership {
+                reissue_expired, ..
+            } => *reissue_expired,
+            Commands::ScheduleAdd {
+                sync_authorization_window,
+                ..
+            } => *sync_authorization_window,
+            Commands::ScheduleList { .. }
+            | Commands::ScheduleRemove { .. }
+            | Commands::ProcessorQueue { .. }
+            | Commands::Status { .. }
+            | Commands::Inspect { .. }
+            | Commands::Diagram { .. }
+            | Commands::Proposal { .. } => false,
+            _ => true,
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum LibraryConfigCommand {
+    /// Change the input account or replace the splits of a splitter library
+    Splitter {
+        #[arg(long, help = "Splitter library address", value_parser = parse_address)]
+        library_address: Address,
+
+        #[arg(long, help = "New input account", value_parser = parse_address)]
+        input_address: Option<Address>,
+
+        #[arg(long, help = "Fixed amount split in the format 'account,denom,amount', replaces all splits", value_parser = parse_split)]
+        split: Vec<SplitInfo>,
+    },
+
+    /// Change the accounts, pool or max spread of an Astroport LPer library
+    Lper {
+        #[arg(long, help = "Astroport LPer library address", value_parser = parse_address)]
+        library_address: Address,
+
+        #[arg(long, help = "New input account", value_parser = parse_address)]
+        input_address: Option<Address>,
+
+        #[arg(long, help = "New output account", value_parser = parse_address)]
+        output_address: Option<Address>,
+
+        #[arg(long, help = "New pool address, trading the same assets with the same pool type", value_parser = parse_address)]
+        pool_address: Option<Address>,
+
+        #[arg(long, help = "Maximum spread when providing liquidity, e.g. 0.01", value_parser = parse_decimal)]
+        max_spread: Option<Decimal>,
+    },
+
+    /// Change the accounts or pool of an Astroport withdrawer library
+    Withdrawer {
+        #[arg(long, help = "Astroport withdrawer library address", value_parser = parse_address)]
+        library_address: Address,
+
+        #[arg(long, help = "New input account", value_parser = parse_address)]
+        input_address: Option<Address>,
+
+        #[arg(long, help = "New output address", value_parser = parse_address)]
+        output_address: Option<Address>,
+
+        #[arg(long, help = "New pool address", value_parser = parse_address)]
+        pool_address: Option<Address>,
+    },
+}
+
+impl LibraryConfigCommand {
+    pub fn library_address(&self) -> &Address {
+        match self {
+            LibraryConfigCommand::Splitter {
+                library_address, ..
+            }
+            | LibraryConfigCommand::Lper {
+                library_address, ..
+            }
+            | LibraryConfigCommand::Withdrawer {
+                library_address, ..
+            } => library_address,
+        }
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ProgramAction {
     Deploy,
     Withdraw,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(ValueEnum, Debug, Clone)]
+pub enum TopologyFormat {
+    Json,
+    Dot,
+}
+
+#[derive(ValueEnum, Debug, Clone)]
+pub enum DiagramFormat {
+    Dot,
+    Mermaid,
+}
+
+#[derive(ValueEnum, Debug, Clone)]
+pub enum AuthorizationModeArg {
+    /// Permissioned for the tool operator only
+    Operator,
+    Permissionless,
+    Permissioned,
+}
+
+impl AuthorizationModeArg {
+    pub fn to_spec(
+        &self,
+        addresses: &[Address],
+        call_limit: Option<u128>,
+    ) -> AuthorizationModeSpec {
+        match self {
+            AuthorizationModeArg::Operator => AuthorizationModeSpec::Operator,
+            AuthorizationModeArg::Permissionless => AuthorizationModeSpec::Permissionless,
+            AuthorizationModeArg::Permissioned => AuthorizationModeSpec::Permissioned {
+                addresses: addresses.to_vec(),
+                call_limit,
+            },
+        }
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone)]
+pub enum AuthorizationPriority {
+    Medium,
+    High,
+}
+
+impl From<AuthorizationPriority> for Priority {
+    fn from(priority: AuthorizationPriority) -> Self {
+        match priority {
+            AuthorizationPriority::Medium => Priority::Medium,
+            AuthorizationPriority::High => Priority::High,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PoolInfo {
-    pub address: String,
+    pub address: Address,
     pub amount_a: u128,
     pub amount_b: u128,
     pub denom_a: String,
     pub denom_b: String,
     pub pool_type: PoolType,
+    pub name: Option<String>,
+}
+
+impl PoolInfo {
+    /// Identifier used in per-pool authorization labels (name if given, address otherwise)
+    pub fn label_id(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.address)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SplitInfo {
+    pub account: Address,
+    pub denom: String,
+    pub amount: u128,
+}
+
+fn parse_split(s: &str) -> Result<SplitInfo, String> {
+    let parts: Vec<&str> = s.split(',').collect();
+    if parts.len() != 3 {
+        return Err("Invalid format. Expected: account,denom,amount".to_string());
+    }
+
+    Ok(SplitInfo {
+        account: parse_address(parts[0])?,
+        denom: parts[1].to_string(),
+        amount: parts[2]
+            .parse()
+            .map_err(|_| "Invalid amount format".to_string())?,
+    })
+}
+
+fn parse_decimal(s: &str) -> Result<Decimal, String> {
+    s.parse().map_err(|_| "Invalid decimal format".to_string())
 }
 
 fn parse_pool(s: &str) -> Result<PoolInfo, String> {
     let parts: Vec<&str> = s.split(',').collect();
-    if parts.len() != 6 {
+    if parts.len() != 6 && parts.len() != 7 {
         return Err(
-            "Invalid format. Expected: address,amount_a,amount_b,denom_a,denom_b,pool_type"
+            "Invalid format. Expected: address,amount_a,amount_b,denom_a,denom_b,pool_type[,name]"
                 .to_string(),
         );
     }
@@ -80,7 +757,7 @@ fn parse_pool(s: &str) -> Result<PoolInfo, String> {
     };
 
     Ok(PoolInfo {
-        address: parts[0].to_string(),
+        address: parse_address(parts[0])?,
         amount_a: parts[1]
             .parse()
             .map_err(|_| "Invalid amount_a format".to_string())?,
@@ -90,5 +767,154 @@ fn parse_pool(s: &str) -> Result<PoolInfo, String> {
         denom_a: parts[3].to_string(),
         denom_b: parts[4].to_string(),
         pool_type,
+        name: parts.get(6).map(|name| parse_pool_name(name)).transpose()?,
     })
 }
+
+/// Pool names end up in authorization labels between `_` delimiters, so they are
+/// restricted to letters, digits and `-`
+fn parse_pool_name(name: &str) -> Result<String, String> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(format!(
+            "Invalid pool name {}. Expected letters, digits and '-'",
+            name
+        ));
+    }
+
+    Ok(name.to_string())
+}
+
+pub fn parse_expiration(s: &str) -> Result<Expiration, String> {
+    if s == "never" {
+        return Ok(Expiration::Never {});
+    }
+
+    match s.split_once(':') {
+        Some(("time", seconds)) => Ok(Expiration::AtTime(Timestamp::from_seconds(
+            seconds
+                .parse()
+                .map_err(|_| "Invalid time format".to_string())?,
+        ))),
+        Some(("height", height)) => Ok(Expiration::AtHeight(
+            height
+                .parse()
+                .map_err(|_| "Invalid height format".to_string())?,
+        )),
+        _ => Err(
+            "Invalid format. Expected: 'time:<unix_seconds>', 'height:<block>' or 'never'"
+                .to_string(),
+        ),
+    }
+}
+
+fn parse_mint(s: &str) -> Result<Mint, String> {
+    let (address, amount) = match s.split_once(':') {
+        Some((address, amount)) => (
+            address,
+            amount
+                .parse::<u128>()
+                .map_err(|_| "Invalid amount format".to_string())?,
+        ),
+        None => (s, 1),
+    };
+
+    Ok(Mint {
+        address: parse_address(address)?.to_string(),
+        amount: Uint128::from(amount),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POOL_ADDRESS: &str = "neutron1qqqsyqcyq5rqwzqfpg9scrgwpugpzysn8fcct7";
+
+    #[test]
+    fn parse_pool_with_name() {
+        let pool = parse_pool(&format!(
+            "{},10,20,untrn,uusdc,xyk_native,ntrn-usdc",
+            POOL_ADDRESS
+        ))
+        .unwrap();
+        assert_eq!(pool.address.as_str(), POOL_ADDRESS);
+        assert_eq!((pool.amount_a, pool.amount_b), (10, 20));
+        assert_eq!(
+            (pool.denom_a.as_str(), pool.denom_b.as_str()),
+            ("untrn", "uusdc")
+        );
+        assert_eq!(pool.label_id(), "ntrn-usdc");
+    }
+
+    #[test]
+    fn parse_pool_defaults_label_to_address() {
+        let pool = parse_pool(&format!("{},10,20,untrn,uusdc,stable_cw20", POOL_ADDRESS)).unwrap();
+        assert_eq!(pool.name, None);
+        assert_eq!(pool.label_id(), POOL_ADDRESS);
+    }
+
+    #[test]
+    fn parse_pool_rejects_invalid_input() {
+        assert!(parse_pool(&format!("{},10,20,untrn,uusdc", POOL_ADDRESS)).is_err());
+        assert!(parse_pool(&format!("{},x,20,untrn,uusdc,xyk_native", POOL_ADDRESS)).is_err());
+        assert!(parse_pool(&format!("{},10,20,untrn,uusdc,unknown", POOL_ADDRESS)).is_err());
+        assert!(
+            parse_pool("cosmos1qqqsyqcyq5rqwzqfpg9scrgwpugpzysnrk363e,10,20,a,b,xyk_native")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn parse_pool_rejects_unsafe_names() {
+        for name in ["usdc_a", "usdc.a", "", "usdc a"] {
+            assert!(
+                parse_pool(&format!(
+                    "{},10,20,untrn,uusdc,xyk_native,{}",
+                    POOL_ADDRESS, name
+                ))
+                .is_err(),
+                "{} should be rejected",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn parse_expiration_formats() {
+        assert_eq!(parse_expiration("never").unwrap(), Expiration::Never {});
+        assert_eq!(
+            parse_expiration("time:1700000000").unwrap(),
+            Expiration::AtTime(Timestamp::from_seconds(1_700_000_000))
+        );
+        assert_eq!(
+            parse_expiration("height:42").unwrap(),
+            Expiration::AtHeight(42)
+        );
+        assert!(parse_expiration("time:soon").is_err());
+        assert!(parse_expiration("1700000000").is_err());
+    }
+
+    #[test]
+    fn parse_split_fields() {
+        let split = parse_split(&format!("{},untrn,10", POOL_ADDRESS)).unwrap();
+        assert_eq!(split.account.as_str(), POOL_ADDRESS);
+        assert_eq!(split.denom, "untrn");
+        assert_eq!(split.amount, 10);
+
+        assert!(parse_split(&format!("{},untrn", POOL_ADDRESS)).is_err());
+        assert!(parse_split(&format!("{},untrn,ten", POOL_ADDRESS)).is_err());
+        assert!(parse_split("neutron1invalid,untrn,10").is_err());
+    }
+
+    #[test]
+    fn parse_mint_defaults_to_one() {
+        let mint = parse_mint(POOL_ADDRESS).unwrap();
+        assert_eq!(mint.address, POOL_ADDRESS);
+        assert_eq!(mint.amount, Uint128::one());
+
+        let mint = parse_mint(&format!("{}:5", POOL_ADDRESS)).unwrap();
+        assert_eq!(mint.amount, Uint128::new(5));
+
+        assert!(parse_mint(&format!("{}:many", POOL_ADDRESS)).is_err());
+    }
+}
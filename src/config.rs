@@ -1,5 +1,20 @@
-use anyhow::{Context, Result};
+use crate::address::Address;
+use anyhow::{anyhow, Context, Result};
 use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// How transactions are signed
+#[derive(Debug, Clone, PartialEq)]
+pub enum SignerKind {
+    /// Operator key in the keyring of the node binary
+    Keyring,
+    /// Mnemonic from an environment secret or an encrypted file
+    Mnemonic,
+    /// Unsigned transactions are written to files and signed on another machine
+    Offline,
+}
 
 #[derive(Debug, Clone)]
 pub struct Config {
@@ -9,9 +24,9 @@ pub struct Config {
     pub astro_withdraw_code_id: u64,
     pub authorization_code_id: u64,
     pub processor_code_id: u64,
-    pub tool_operator_address: String,
+    pub tool_operator_address: Address,
     pub tool_operator_moniker: String,
-    pub neutron_dao_committee_address: String,
+    pub neutron_dao_committee_address: Address,
     // flags related to communication with neutron node
     pub neutron_rpc: String,
     pub neutron_binary: String,
@@ -19,9 +34,39 @@ pub struct Config {
     pub home: String,
     pub gas_price: String,
     pub gas_adjustment: String,
+    // keyring holding the operator key (os, file, pass or test) and how transactions are signed
+    pub keyring_backend: String,
+    pub keyring_passphrase: Option<String>,
+    pub signer: SignerKind,
+    // directory the offline signer exchanges transaction files in, set with --offline-dir
+    pub offline_dir: Option<PathBuf>,
+    // how long the offline signer waits for the signed transaction
+    pub offline_sign_timeout: Duration,
+    // optional feegrant granter paying the fees, and fee payer if it is not the operator
+    pub fee_granter: Option<Address>,
+    pub fee_payer: Option<Address>,
+    // bech32 prefix of the chain's addresses, used for address prediction and validation
+    pub bech32_prefix: String,
+    // optional expiry of ownership transfers to the DAO committee
+    pub ownership_transfer_expiry_seconds: Option<u64>,
+    // limits for packing multiple messages into one transaction
+    pub max_msgs_per_tx: usize,
+    pub max_gas_per_tx: u64,
+}
+
+const KEYRING_BACKENDS: [&str; 4] = ["os", "file", "pass", "test"];
+const DEFAULT_BECH32_PREFIX: &str = "neutron";
+const DEFAULT_MAX_MSGS_PER_TX: usize = 25;
+const DEFAULT_MAX_GAS_PER_TX: u64 = 15_000_000;
+const DEFAULT_OFFLINE_SIGN_TIMEOUT_SECONDS: u64 = 3600;
+
+/// Bech32 prefix of the network, also used to validate CLI address arguments
+pub fn configured_bech32_prefix() -> String {
+    env::var("LD_TOOL_BECH32_PREFIX").unwrap_or_else(|_| DEFAULT_BECH32_PREFIX.to_string())
 }
 
 pub fn load_config() -> Result<Config> {
+    let bech32_prefix = configured_bech32_prefix();
     let base_account_code_id_str = env::var("LD_TOOL_BASE_ACCOUNT_CODE_ID")
         .context("LD_TOOL_BASE_ACCOUNT_CODE_ID environment variable is required")?;
     let spliter_code_id_str = env::var("LD_TOOL_SPLITER_CODE_ID")
@@ -54,12 +99,20 @@ pub fn load_config() -> Result<Config> {
         processor_code_id: processor_code_id_str
             .parse()
             .context("Failed to parse LD_TOOL_PROCESSOR_CODE_ID")?,
-        tool_operator_address: env::var("LD_TOOL_OPERATOR_ADDRESS")
-            .context("LD_TOOL_OPERATOR_ADDRESS environment variable is required")?,
+        tool_operator_address: Address::parse(
+            &env::var("LD_TOOL_OPERATOR_ADDRESS")
+                .context("LD_TOOL_OPERATOR_ADDRESS environment variable is required")?,
+            &bech32_prefix,
+        )
+        .context("Failed to parse LD_TOOL_OPERATOR_ADDRESS")?,
         tool_operator_moniker: env::var("LD_TOOL_OPERATOR_MONIKER")
             .context("LD_TOOL_OPERATOR_MONIKER environment variable is required")?,
-        neutron_dao_committee_address: env::var("LD_TOOL_DAO_COMMITTEE_ADDRESS")
-            .context("LD_TOOL_DAO_COMMITTEE_ADDRESS environment variable is required")?,
+        neutron_dao_committee_address: Address::parse(
+            &env::var("LD_TOOL_DAO_COMMITTEE_ADDRESS")
+                .context("LD_TOOL_DAO_COMMITTEE_ADDRESS environment variable is required")?,
+            &bech32_prefix,
+        )
+        .context("Failed to parse LD_TOOL_DAO_COMMITTEE_ADDRESS")?,
         neutron_rpc: env::var("LD_TOOL_NEUTRON_NODE_RPC")
             .context("LD_TOOL_NEUTRON_NODE_RPC environment variable is required")?,
         neutron_binary: env::var("LD_TOOL_NEUTRON_NODE_BINARY")
@@ -72,5 +125,92 @@ pub fn load_config() -> Result<Config> {
             .context("LD_TOOL_GAS_ADJUSTMENT environment variable is required")?,
         gas_price: env::var("LD_TOOL_GAS_PRICE")
             .context("LD_TOOL_GAS_PRICE environment variable is required")?,
+        keyring_backend: parse_keyring_backend(
+            env::var("LD_TOOL_KEYRING_BACKEND").unwrap_or_else(|_| "os".to_string()),
+        )?,
+        keyring_passphrase: load_keyring_passphrase()?,
+        signer: parse_signer(env::var("LD_TOOL_SIGNER").unwrap_or_else(|_| "keyring".to_string()))?,
+        offline_dir: None,
+        offline_sign_timeout: Duration::from_secs(
+            env::var("LD_TOOL_OFFLINE_SIGN_TIMEOUT_SECONDS")
+                .ok()
+                .map(|timeout| timeout.parse())
+                .transpose()
+                .context("Failed to parse LD_TOOL_OFFLINE_SIGN_TIMEOUT_SECONDS")?
+                .unwrap_or(DEFAULT_OFFLINE_SIGN_TIMEOUT_SECONDS),
+        ),
+        fee_granter: env::var("LD_TOOL_FEE_GRANTER")
+            .ok()
+            .map(|granter| Address::parse(&granter, &bech32_prefix))
+            .transpose()
+            .context("Failed to parse LD_TOOL_FEE_GRANTER")?,
+        fee_payer: env::var("LD_TOOL_FEE_PAYER")
+            .ok()
+            .map(|payer| Address::parse(&payer, &bech32_prefix))
+            .transpose()
+            .context("Failed to parse LD_TOOL_FEE_PAYER")?,
+        bech32_prefix,
+        ownership_transfer_expiry_seconds: env::var("LD_TOOL_OWNERSHIP_TRANSFER_EXPIRY_SECONDS")
+            .ok()
+            .map(|expiry| expiry.parse())
+            .transpose()
+            .context("Failed to parse LD_TOOL_OWNERSHIP_TRANSFER_EXPIRY_SECONDS")?,
+        max_msgs_per_tx: env::var("LD_TOOL_MAX_MSGS_PER_TX")
+            .ok()
+            .map(|max| max.parse())
+            .transpose()
+            .context("Failed to parse LD_TOOL_MAX_MSGS_PER_TX")?
+            .unwrap_or(DEFAULT_MAX_MSGS_PER_TX),
+        max_gas_per_tx: env::var("LD_TOOL_MAX_GAS_PER_TX")
+            .ok()
+            .map(|max| max.parse())
+            .transpose()
+            .context("Failed to parse LD_TOOL_MAX_GAS_PER_TX")?
+            .unwrap_or(DEFAULT_MAX_GAS_PER_TX),
     })
 }
+
+fn parse_keyring_backend(keyring_backend: String) -> Result<String> {
+    if !KEYRING_BACKENDS.contains(&keyring_backend.as_str()) {
+        return Err(anyhow!(
+            "Invalid LD_TOOL_KEYRING_BACKEND {}, expected one of {}",
+            keyring_backend,
+            KEYRING_BACKENDS.join(", ")
+        ));
+    }
+
+    Ok(keyring_backend)
+}
+
+/// Reads the passphrase of the `file` keyring from `LD_TOOL_KEYRING_PASSPHRASE_FILE`.
+/// `LD_TOOL_KEYRING_PASSPHRASE` is still accepted but insecure: the environment of a process
+/// is readable by other processes of the same user and tends to leak into shell histories
+/// and CI logs. Without either, the passphrase is asked on the terminal when it is needed.
+fn load_keyring_passphrase() -> Result<Option<String>> {
+    if let Ok(passphrase_file) = env::var("LD_TOOL_KEYRING_PASSPHRASE_FILE") {
+        let passphrase = fs::read_to_string(&passphrase_file).with_context(|| {
+            format!("Failed to read keyring passphrase file {}", passphrase_file)
+        })?;
+        return Ok(Some(passphrase.trim_end_matches(['\r', '\n']).to_string()));
+    }
+
+    let passphrase = env::var("LD_TOOL_KEYRING_PASSPHRASE").ok();
+    if passphrase.is_some() {
+        println!(
+            "Warning: LD_TOOL_KEYRING_PASSPHRASE exposes the keyring passphrase to other processes, use LD_TOOL_KEYRING_PASSPHRASE_FILE instead"
+        );
+    }
+    Ok(passphrase)
+}
+
+fn parse_signer(signer: String) -> Result<SignerKind> {
+    match signer.as_str() {
+        "keyring" => Ok(SignerKind::Keyring),
+        "mnemonic" => Ok(SignerKind::Mnemonic),
+        "offline" => Ok(SignerKind::Offline),
+        _ => Err(anyhow!(
+            "Invalid LD_TOOL_SIGNER {}, expected keyring, mnemonic or offline",
+            signer
+        )),
+    }
+}
@@ -0,0 +1,195 @@
+use crate::config::Config;
+use crate::metrics::{serve_metrics, Metrics};
+use crate::processor::{generate_tick, get_queue, QUEUE_PRIORITIES};
+use crate::wasm::{broadcast_msgs, tx_gas_used, TxFailed};
+use anyhow::{Error, Result};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+pub struct TickDaemonOptions {
+    pub processors: Vec<String>,
+    pub poll_interval: Duration,
+    /// Pause after a failed tick before ticking the same processor again
+    pub failure_cooldown: Duration,
+    /// Pause between ticks of a batch that is waiting for a retry
+    pub retry_cooldown: Duration,
+    /// Total gas the daemon may spend on ticks, unlimited if not set
+    pub gas_budget: Option<u64>,
+    pub metrics_address: Option<String>,
+}
+
+#[derive(Default)]
+struct ProcessorState {
+    cooldown_until: Option<Instant>,
+    last_retry_tick: HashMap<u64, Instant>,
+}
+
+/// Watches the processors and ticks every processor whose queue is not empty
+pub fn run_tick_daemon(options: &TickDaemonOptions, config: &Config) -> Result<()> {
+    let metrics = Arc::new(Metrics::default());
+    if let Some(metrics_address) = &options.metrics_address {
+        serve_metrics(metrics_address, metrics.clone())?;
+    }
+
+    let mut states: HashMap<String, ProcessorState> = HashMap::new();
+    let mut gas_spent: u64 = 0;
+
+    println!(
+        "Watching {} processor(s) every {}s ...",
+        options.processors.len(),
+        options.poll_interval.as_secs()
+    );
+
+    loop {
+        for processor_address in &options.processors {
+            let state = states.entry(processor_address.clone()).or_default();
+            match watch_processor(
+                config,
+                options,
+                processor_address,
+                state,
+                &metrics,
+                &mut gas_spent,
+            ) {
+                Ok(TickOutcome::Done) => {}
+                Ok(TickOutcome::OverBudget(gas_estimate)) => {
+                    println!(
+                        "Ticking processor {} needs {} gas, more than what is left of the gas budget ({} of {} spent), stopping",
+                        processor_address,
+                        gas_estimate,
+                        gas_spent,
+                        options.gas_budget.unwrap_or_default()
+                    );
+                    return Ok(());
+                }
+                Err(e) => {
+                    println!("Ticking processor {} failed: {}", processor_address, e);
+                    metrics.inc_counter(
+                        "ld_tool_tick_failures_total",
+                        &processor_label(processor_address),
+                        1.0,
+                    );
+                    state.cooldown_until = Some(Instant::now() + options.failure_cooldown);
+                }
+            }
+        }
+
+        if let Some(gas_budget) = options.gas_budget {
+            if gas_spent >= gas_budget {
+                println!(
+                    "Gas budget of {} exhausted ({} spent), stopping",
+                    gas_budget, gas_spent
+                );
+                return Ok(());
+            }
+        }
+
+        sleep(options.poll_interval);
+    }
+}
+
+enum TickOutcome {
+    /// The processor was ticked, or there was nothing to tick
+    Done,
+    /// The estimated gas of the tick doesn't fit in the rest of the gas budget
+    OverBudget(u64),
+}
+
+fn watch_processor(
+    config: &Config,
+    options: &TickDaemonOptions,
+    processor_address: &str,
+    state: &mut ProcessorState,
+    metrics: &Metrics,
+    gas_spent: &mut u64,
+) -> Result<TickOutcome, Error> {
+    if state
+        .cooldown_until
+        .is_some_and(|cooldown_until| Instant::now() < cooldown_until)
+    {
+        return Ok(TickOutcome::Done);
+    }
+
+    let mut next_batch: Option<Value> = None;
+    let mut queued_ids = HashSet::new();
+    for priority in QUEUE_PRIORITIES {
+        let queue = get_queue(config, processor_address, priority)?;
+        metrics.set_gauge(
+            "ld_tool_queue_depth",
+            &format!(
+                "{},priority=\"{}\"",
+                processor_label(processor_address),
+                priority
+            ),
+            queue.len() as f64,
+        );
+        if next_batch.is_none() {
+            next_batch = queue.first().cloned();
+        }
+        queued_ids.extend(queue.iter().filter_map(|batch| batch["id"].as_u64()));
+    }
+
+    // Forget the retry ticks of batches that left the queue
+    state
+        .last_retry_tick
+        .retain(|batch_id, _| queued_ids.contains(batch_id));
+
+    let Some(batch) = next_batch else {
+        return Ok(TickOutcome::Done);
+    };
+
+    // Batches waiting for a retry are not executed before their cooldown, so don't waste gas on them
+    let batch_id = batch["id"].as_u64().unwrap_or_default();
+    if !batch["retry"].is_null() {
+        if let Some(last_tick) = state.last_retry_tick.get(&batch_id) {
+            if last_tick.elapsed() < options.retry_cooldown {
+                return Ok(TickOutcome::Done);
+            }
+        }
+    }
+
+    // The tick is broadcast with the estimated gas as its limit, so it can't spend more than that
+    let (tick_msg, gas_estimate) = generate_tick(config, processor_address)?;
+    if let Some(gas_budget) = options.gas_budget {
+        if *gas_spent + gas_estimate > gas_budget {
+            return Ok(TickOutcome::OverBudget(gas_estimate));
+        }
+    }
+    if !batch["retry"].is_null() {
+        state.last_retry_tick.insert(batch_id, Instant::now());
+    }
+
+    println!(
+        "Ticking processor {} for execution {} ...",
+        processor_address, batch_id
+    );
+    let result = broadcast_msgs(&[tick_msg], gas_estimate, config);
+    // Ticks that fail in the block pay for their gas too, so they count against the budget
+    let gas_used = match &result {
+        Ok(tx_data) => tx_gas_used(tx_data),
+        Err(e) => e
+            .downcast_ref::<TxFailed>()
+            .map_or(0, |failed| failed.gas_used),
+    };
+
+    *gas_spent += gas_used;
+    metrics.inc_counter(
+        "ld_tool_ticks_sent_total",
+        &processor_label(processor_address),
+        1.0,
+    );
+    metrics.inc_counter(
+        "ld_tool_tick_gas_used_total",
+        &processor_label(processor_address),
+        gas_used as f64,
+    );
+
+    result.map(|_| TickOutcome::Done)
+}
+
+fn processor_label(processor_address: &str) -> String {
+    format!("processor=\"{}\"", processor_address)
+}
@@ -0,0 +1,90 @@
+use crate::batch::TxBatch;
+use crate::config::Config;
+use anyhow::Result;
+use cosmwasm_std::{BankMsg, Binary, Coin, CosmosMsg, WasmMsg};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+/// A message to be executed on a contract, either directly or through a DAO proposal
+pub struct ContractMsg {
+    pub contract_address: String,
+    pub msg: Value,
+}
+
+impl ContractMsg {
+    pub fn new<T: Serialize>(contract_address: &str, msg: &T) -> Result<Self> {
+        Ok(ContractMsg {
+            contract_address: contract_address.to_string(),
+            msg: serde_json::to_value(msg)?,
+        })
+    }
+}
+
+/// Builds the `wasm.execute` CosmosMsg used inside DAO proposals
+pub fn build_wasm_execute_msg(contract_msg: &ContractMsg) -> Result<CosmosMsg> {
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: contract_msg.contract_address.to_string(),
+        msg: Binary::from(serde_json::to_vec(&contract_msg.msg)?),
+        funds: vec![],
+    }))
+}
+
+/// Builds the `bank.send` CosmosMsg paying out of the DAO treasury
+pub fn build_bank_send_msg(to_address: &str, amount: &[Coin]) -> CosmosMsg {
+    CosmosMsg::Bank(BankMsg::Send {
+        to_address: to_address.to_string(),
+        amount: amount.to_vec(),
+    })
+}
+
+/// Builds a proposal payload with the given messages, ready to be submitted to the DAO
+pub fn build_proposal(title: &str, description: &str, msgs: &[ContractMsg]) -> Result<Value> {
+    let cosmos_msgs = msgs
+        .iter()
+        .map(build_wasm_execute_msg)
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(build_cosmos_proposal(title, description, &cosmos_msgs))
+}
+
+/// Builds a proposal payload with any CosmosMsgs, such as bank sends from the treasury
+pub fn build_cosmos_proposal(title: &str, description: &str, msgs: &[CosmosMsg]) -> Value {
+    json!({
+        "title": title,
+        "description": description,
+        "msgs": msgs,
+    })
+}
+
+pub fn print_proposal(title: &str, description: &str, msgs: &[ContractMsg]) -> Result<()> {
+    let proposal = build_proposal(title, description, msgs)?;
+    println!("{}", serde_json::to_string_pretty(&proposal)?);
+    Ok(())
+}
+
+pub fn print_cosmos_proposal(title: &str, description: &str, msgs: &[CosmosMsg]) -> Result<()> {
+    let proposal = build_cosmos_proposal(title, description, msgs);
+    println!("{}", serde_json::to_string_pretty(&proposal)?);
+    Ok(())
+}
+
+/// Executes the messages with the operator key, or prints them as a DAO proposal
+/// when the contracts are owned by the DAO committee
+pub fn execute_or_propose(
+    title: &str,
+    description: &str,
+    msgs: &[ContractMsg],
+    as_proposal: bool,
+    config: &Config,
+) -> Result<()> {
+    if as_proposal {
+        return print_proposal(title, description, msgs);
+    }
+
+    let mut batch = TxBatch::new(config);
+    for contract_msg in msgs {
+        batch.execute(&contract_msg.contract_address, &contract_msg.msg)?;
+    }
+
+    batch.broadcast()
+}
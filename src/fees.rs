@@ -0,0 +1,272 @@
+use crate::config::Config;
+use crate::wasm::{get_balances, get_fee_allowance};
+use anyhow::{anyhow, Error, Result};
+use chrono::{DateTime, Utc};
+use cosmwasm_std::{Coin, Decimal, Uint128};
+use serde_json::Value;
+use std::str::FromStr;
+
+const BASIC_ALLOWANCE: &str = "/cosmos.feegrant.v1beta1.BasicAllowance";
+const PERIODIC_ALLOWANCE: &str = "/cosmos.feegrant.v1beta1.PeriodicAllowance";
+const ALLOWED_MSG_ALLOWANCE: &str = "/cosmos.feegrant.v1beta1.AllowedMsgAllowance";
+
+// Steps that call contracts created by the earlier steps of a deployment can't be simulated
+// before those contracts exist, so they are estimated per message. The figures are generous
+// upper bounds of what these messages use, including their share of the transaction overhead,
+// so the estimate errs on the side of too much gas.
+pub const CREATE_AUTHORIZATIONS_BASE_GAS: u64 = 200_000;
+pub const GAS_PER_AUTHORIZATION: u64 = 200_000;
+pub const GAS_PER_OWNERSHIP_TRANSFER: u64 = 100_000;
+
+/// Computes the fee for the gas from a gas price such as `0.0053untrn`
+pub fn fee_for_gas(gas: u64, gas_price: &str) -> Result<Coin, Error> {
+    let denom_start = gas_price
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .ok_or_else(|| anyhow!("Gas price {} has no denom", gas_price))?;
+    let (price, denom) = gas_price.split_at(denom_start);
+    let price = Decimal::from_str(price).map_err(|_| anyhow!("Invalid gas price {}", gas_price))?;
+
+    Ok(Coin::new(Uint128::from(gas).mul_ceil(price), denom))
+}
+
+/// Gas of creating the authorizations of a new program
+pub fn create_authorizations_gas(authorizations: usize) -> u64 {
+    CREATE_AUTHORIZATIONS_BASE_GAS + GAS_PER_AUTHORIZATION * authorizations as u64
+}
+
+/// Gas of transferring the ownership of the given number of contracts
+pub fn ownership_transfers_gas(transfers: usize) -> u64 {
+    GAS_PER_OWNERSHIP_TRANSFER * transfers as u64
+}
+
+/// Gas estimate of a deployment step, simulated or estimated per message
+pub struct StepEstimate {
+    pub step: String,
+    pub msgs: usize,
+    pub txs: usize,
+    pub gas: u64,
+    pub simulated: bool,
+}
+
+/// Prints the gas and fee of every step and checks that the fees can be paid
+pub fn print_estimate(config: &Config, steps: &[StepEstimate]) -> Result<()> {
+    println!("== Gas estimate ==");
+    let mut total_gas = 0;
+    for step in steps {
+        total_gas += step.gas;
+        println!(
+            "{} | {} message(s) in {} tx(s) | gas: {} ({}) | fee: {}",
+            step.step,
+            step.msgs,
+            step.txs,
+            step.gas,
+            if step.simulated {
+                "simulated"
+            } else {
+                "estimated per message"
+            },
+            fee_for_gas(step.gas, &config.gas_price)?
+        );
+    }
+
+    let total_fee = fee_for_gas(total_gas, &config.gas_price)?;
+    println!(
+        "Total | gas: {} | fee: {} at {}",
+        total_gas, total_fee, config.gas_price
+    );
+
+    if config.fee_granter.is_some() {
+        check_fee_allowance(config, total_gas, &[])?;
+        println!("Fee allowance covers the estimated fee");
+        return Ok(());
+    }
+
+    let balance = get_balances(config, &config.tool_operator_address)?
+        .into_iter()
+        .find(|coin| coin.denom == total_fee.denom)
+        .map(|coin| coin.amount)
+        .unwrap_or_default();
+    println!(
+        "Operator balance: {}{} ({})",
+        balance,
+        total_fee.denom,
+        if balance >= total_fee.amount {
+            "enough for the estimated fee"
+        } else {
+            "NOT enough for the estimated fee"
+        }
+    );
+
+    Ok(())
+}
+
+/// Checks that the configured fee granter has a feegrant allowance for the fee payer
+/// that allows the message types and covers the fee of the estimated gas
+pub fn check_fee_allowance(config: &Config, gas: u64, msg_types: &[String]) -> Result<()> {
+    let Some(fee_granter) = &config.fee_granter else {
+        return Ok(());
+    };
+    let grantee = config
+        .fee_payer
+        .as_ref()
+        .unwrap_or(&config.tool_operator_address);
+
+    let allowance = get_fee_allowance(config, fee_granter, grantee)?
+        .ok_or_else(|| anyhow!("No fee allowance from {} to {}", fee_granter, grantee))?;
+    let fee = fee_for_gas(gas, &config.gas_price)?;
+    check_allowance(&allowance, &fee, msg_types)
+        .map_err(|e| anyhow!("Fee allowance from {} to {}: {}", fee_granter, grantee, e))
+}
+
+fn check_allowance(allowance: &Value, fee: &Coin, msg_types: &[String]) -> Result<()> {
+    match allowance["@type"].as_str().unwrap_or_default() {
+        BASIC_ALLOWANCE => {
+            check_expiration(&allowance["expiration"])?;
+            check_spend_limit(&allowance["spend_limit"], fee)
+        }
+        PERIODIC_ALLOWANCE => {
+            check_allowance(&allowance["basic"], fee, msg_types)?;
+            check_spend_limit(&allowance["period_can_spend"], fee)
+        }
+        ALLOWED_MSG_ALLOWANCE => {
+            let allowed_messages: Vec<&str> = allowance["allowed_messages"]
+                .as_array()
+                .map(|msgs| msgs.iter().filter_map(Value::as_str).collect())
+                .unwrap_or_default();
+            if let Some(msg_type) = msg_types
+                .iter()
+                .find(|msg_type| !allowed_messages.contains(&msg_type.as_str()))
+            {
+                return Err(anyhow!("message type {} is not allowed", msg_type));
+            }
+            check_allowance(&allowance["allowance"], fee, msg_types)
+        }
+        allowance_type => Err(anyhow!("unsupported allowance type {}", allowance_type)),
+    }
+}
+
+fn check_expiration(expiration: &Value) -> Result<()> {
+    if let Some(expiration) = expiration.as_str() {
+        let expiration = DateTime::parse_from_rfc3339(expiration)?;
+        if expiration < Utc::now() {
+            return Err(anyhow!("expired at {}", expiration));
+        }
+    }
+
+    Ok(())
+}
+
+/// An empty spend limit means the allowance is unlimited
+fn check_spend_limit(spend_limit: &Value, fee: &Coin) -> Result<()> {
+    let spend_limit: Vec<Coin> = serde_json::from_value(spend_limit.clone()).unwrap_or_default();
+    if spend_limit.is_empty() {
+        return Ok(());
+    }
+
+    let available = spend_limit
+        .iter()
+        .find(|coin| coin.denom == fee.denom)
+        .map(|coin| coin.amount)
+        .unwrap_or_default();
+    if available < fee.amount {
+        return Err(anyhow!(
+            "{}{} left, {} needed for the estimated gas",
+            available,
+            fee.denom,
+            fee
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn basic(spend_limit: Value, expiration: Value) -> Value {
+        json!({
+            "@type": BASIC_ALLOWANCE,
+            "spend_limit": spend_limit,
+            "expiration": expiration,
+        })
+    }
+
+    #[test]
+    fn fee_is_rounded_up() {
+        assert_eq!(
+            fee_for_gas(1_000_000, "0.0053untrn").unwrap(),
+            Coin::new(5300u128, "untrn")
+        );
+        assert_eq!(
+            fee_for_gas(3, "0.5untrn").unwrap(),
+            Coin::new(2u128, "untrn")
+        );
+        assert_eq!(
+            fee_for_gas(10, "1ibc/ABC").unwrap(),
+            Coin::new(10u128, "ibc/ABC")
+        );
+        // 0.07 is not exact in binary floating point, 100 * 0.07 must still be 7
+        assert_eq!(
+            fee_for_gas(100, "0.07untrn").unwrap(),
+            Coin::new(7u128, "untrn")
+        );
+        assert_eq!(
+            fee_for_gas(u64::MAX, "0.0053untrn").unwrap(),
+            Coin::new(97_767_743_590_660_624u128, "untrn")
+        );
+        assert!(fee_for_gas(10, "0.0053").is_err());
+        assert!(fee_for_gas(10, "0..1untrn").is_err());
+    }
+
+    #[test]
+    fn basic_allowance_limits() {
+        let fee = Coin::new(100u128, "untrn");
+        assert!(check_allowance(&basic(json!([]), Value::Null), &fee, &[]).is_ok());
+        assert!(check_allowance(
+            &basic(json!([{"denom": "untrn", "amount": "100"}]), Value::Null),
+            &fee,
+            &[]
+        )
+        .is_ok());
+        assert!(check_allowance(
+            &basic(json!([{"denom": "untrn", "amount": "99"}]), Value::Null),
+            &fee,
+            &[]
+        )
+        .is_err());
+        assert!(check_allowance(
+            &basic(json!([{"denom": "uatom", "amount": "1000"}]), Value::Null),
+            &fee,
+            &[]
+        )
+        .is_err());
+        assert!(
+            check_allowance(&basic(json!([]), json!("2000-01-01T00:00:00Z")), &fee, &[]).is_err()
+        );
+    }
+
+    #[test]
+    fn nested_allowances() {
+        let fee = Coin::new(100u128, "untrn");
+        let periodic = json!({
+            "@type": PERIODIC_ALLOWANCE,
+            "basic": basic(json!([]), Value::Null),
+            "period_can_spend": [{"denom": "untrn", "amount": "50"}],
+        });
+        assert!(check_allowance(&periodic, &fee, &[]).is_err());
+
+        let allowed = json!({
+            "@type": ALLOWED_MSG_ALLOWANCE,
+            "allowed_messages": ["/cosmwasm.wasm.v1.MsgExecuteContract"],
+            "allowance": basic(json!([]), Value::Null),
+        });
+        let execute = vec!["/cosmwasm.wasm.v1.MsgExecuteContract".to_string()];
+        let instantiate = vec!["/cosmwasm.wasm.v1.MsgInstantiateContract2".to_string()];
+        assert!(check_allowance(&allowed, &fee, &execute).is_ok());
+        assert!(check_allowance(&allowed, &fee, &instantiate).is_err());
+
+        assert!(check_allowance(&json!({"@type": "/unknown"}), &fee, &[]).is_err());
+    }
+}
@@ -0,0 +1,121 @@
+use crate::cli::PoolInfo;
+use crate::config::Config;
+use crate::dao::{build_bank_send_msg, ContractMsg};
+use crate::wasm::get_balances;
+use anyhow::{anyhow, Result};
+use cosmwasm_std::Coin;
+use std::collections::BTreeMap;
+
+/// Capital the input account needs for one deploy: the splits of every pool summed per denom
+pub fn required_funds(pools: &[PoolInfo]) -> Vec<Coin> {
+    let mut totals: BTreeMap<&str, u128> = BTreeMap::new();
+    for pool in pools {
+        *totals.entry(&pool.denom_a).or_default() += pool.amount_a;
+        *totals.entry(&pool.denom_b).or_default() += pool.amount_b;
+    }
+
+    totals
+        .into_iter()
+        .map(|(denom, amount)| Coin::new(amount, denom))
+        .collect()
+}
+
+/// Checks that the source holds every required coin and reports what is missing
+pub fn check_source_balances(config: &Config, source: &str, required: &[Coin]) -> Result<()> {
+    let balances = get_balances(config, source)?;
+
+    let mut missing = Vec::new();
+    for coin in required {
+        let available = balances
+            .iter()
+            .find(|balance| balance.denom == coin.denom)
+            .map(|balance| balance.amount)
+            .unwrap_or_default();
+        println!(
+            "{} | required: {} | available: {}",
+            coin.denom, coin.amount, available
+        );
+        if available < coin.amount {
+            missing.push(coin.to_string());
+        }
+    }
+
+    if !missing.is_empty() {
+        return Err(anyhow!(
+            "{} does not hold the required {}",
+            source,
+            missing.join(", ")
+        ));
+    }
+
+    Ok(())
+}
+
+/// Formats coins the way `tx bank send` expects them
+pub fn format_coins(coins: &[Coin]) -> String {
+    coins
+        .iter()
+        .map(|coin| coin.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Lists the balances of the accounts and builds, for every account holding funds, a message
+/// that makes the account send its whole balance to the recipient. Only the account owner
+/// can execute it, which is the DAO committee once ownership was transferred.
+pub fn build_sweep_msgs(
+    config: &Config,
+    accounts: &[String],
+    recipient: &str,
+) -> Result<Vec<ContractMsg>> {
+    let mut sweep_msgs = Vec::new();
+    for account in accounts {
+        let balances = get_balances(config, account)?;
+        if balances.is_empty() {
+            println!("{} | empty", account);
+            continue;
+        }
+        println!("{} | {}", account, format_coins(&balances));
+
+        let execute_msg = valence_account_utils::msg::ExecuteMsg::ExecuteMsg {
+            msgs: vec![build_bank_send_msg(recipient, &balances)],
+        };
+        sweep_msgs.push(ContractMsg::new(account, &execute_msg)?);
+    }
+
+    Ok(sweep_msgs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::address::Address;
+    use valence_astroport_utils::{astroport_native_lp_token, PoolType};
+
+    fn pool(denom_a: &str, amount_a: u128, denom_b: &str, amount_b: u128) -> PoolInfo {
+        PoolInfo {
+            address: Address::parse("neutron1qqqsyqcyq5rqwzqfpg9scrgwpugpzysn8fcct7", "neutron")
+                .unwrap(),
+            amount_a,
+            amount_b,
+            denom_a: denom_a.to_string(),
+            denom_b: denom_b.to_string(),
+            pool_type: PoolType::NativeLpToken(astroport_native_lp_token::PairType::Xyk {}),
+            name: None,
+        }
+    }
+
+    #[test]
+    fn required_funds_sums_per_denom() {
+        let pools = [pool("untrn", 10, "uusdc", 20), pool("uatom", 5, "untrn", 7)];
+        assert_eq!(
+            required_funds(&pools),
+            vec![
+                Coin::new(5u128, "uatom"),
+                Coin::new(17u128, "untrn"),
+                Coin::new(20u128, "uusdc"),
+            ]
+        );
+        assert!(required_funds(&[]).is_empty());
+    }
+}
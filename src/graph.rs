@@ -0,0 +1,175 @@
+/// Directed graph of program contracts and addresses, rendered for audits and proposals
+#[derive(Debug, Default)]
+pub struct Graph {
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+}
+
+#[derive(Debug)]
+struct Node {
+    address: String,
+    label: String,
+}
+
+#[derive(Debug)]
+struct Edge {
+    from: String,
+    to: String,
+    label: String,
+}
+
+impl Graph {
+    /// Adds a node for the address, unless it is empty or already in the graph
+    pub fn add_node(&mut self, address: &str, role: &str) {
+        if address.is_empty() || self.nodes.iter().any(|node| node.address == address) {
+            return;
+        }
+
+        self.nodes.push(Node {
+            address: address.to_string(),
+            label: format!("{}\n{}", role, address),
+        });
+    }
+
+    pub fn add_edge(&mut self, from: &str, to: &str, label: &str) {
+        if from.is_empty() || to.is_empty() {
+            return;
+        }
+
+        self.edges.push(Edge {
+            from: from.to_string(),
+            to: to.to_string(),
+            label: label.to_string(),
+        });
+    }
+
+    /// Renders the graph in Graphviz DOT, with the addresses as node ids
+    pub fn to_dot(&self, name: &str) -> String {
+        let mut dot = format!("digraph \"{}\" {{\n", escape(name));
+        dot.push_str("  rankdir=LR;\n  node [shape=box];\n");
+        for node in &self.nodes {
+            dot.push_str(&format!(
+                "  \"{}\" [label=\"{}\"];\n",
+                escape(&node.address),
+                escape(&node.label)
+            ));
+        }
+        for edge in &self.edges {
+            if edge.label.is_empty() {
+                dot.push_str(&format!(
+                    "  \"{}\" -> \"{}\";\n",
+                    escape(&edge.from),
+                    escape(&edge.to)
+                ));
+            } else {
+                dot.push_str(&format!(
+                    "  \"{}\" -> \"{}\" [label=\"{}\"];\n",
+                    escape(&edge.from),
+                    escape(&edge.to),
+                    escape(&edge.label)
+                ));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Renders the graph as a Mermaid flowchart. Mermaid ids can't contain every character
+    /// of an address, so nodes get positional ids.
+    pub fn to_mermaid(&self) -> String {
+        let node_id = |address: &str| {
+            self.nodes
+                .iter()
+                .position(|node| node.address == address)
+                .map(|i| format!("n{}", i))
+                .unwrap_or_default()
+        };
+
+        let mut mermaid = "flowchart LR\n".to_string();
+        for (i, node) in self.nodes.iter().enumerate() {
+            mermaid.push_str(&format!("  n{}[\"{}\"]\n", i, escape_mermaid(&node.label)));
+        }
+        for edge in &self.edges {
+            let (from, to) = (node_id(&edge.from), node_id(&edge.to));
+            if from.is_empty() || to.is_empty() {
+                continue;
+            }
+            if edge.label.is_empty() {
+                mermaid.push_str(&format!("  {} --> {}\n", from, to));
+            } else {
+                mermaid.push_str(&format!(
+                    "  {} -->|\"{}\"| {}\n",
+                    from,
+                    escape_mermaid(&edge.label),
+                    to
+                ));
+            }
+        }
+        mermaid
+    }
+}
+
+fn escape_mermaid(text: &str) -> String {
+    text.replace('"', "#quot;").replace('\n', "<br/>")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dot_escapes_names_labels_and_ids() {
+        let mut graph = Graph::default();
+        graph.add_node("a\"b", "input");
+        graph.add_node("c", "out\\put");
+        graph.add_node("", "ignored");
+        graph.add_edge("a\"b", "c", "splits \"x\"");
+        graph.add_edge("c", "a\"b", "");
+
+        assert_eq!(
+            graph.to_dot("prog \"1\""),
+            "digraph \"prog \\\"1\\\"\" {\n  rankdir=LR;\n  node [shape=box];\n  \
+            \"a\\\"b\" [label=\"input\\na\\\"b\"];\n  \
+            \"c\" [label=\"out\\\\put\\nc\"];\n  \
+            \"a\\\"b\" -> \"c\" [label=\"splits \\\"x\\\"\"];\n  \
+            \"c\" -> \"a\\\"b\";\n}\n"
+        );
+    }
+
+    #[test]
+    fn nodes_are_added_once() {
+        let mut graph = Graph::default();
+        graph.add_node("a", "input");
+        graph.add_node("a", "output");
+        graph.add_edge("a", "", "dropped");
+        assert_eq!(
+            graph.to_dot("p"),
+            "digraph \"p\" {\n  rankdir=LR;\n  node [shape=box];\n  \"a\" [label=\"input\\na\"];\n}\n"
+        );
+    }
+
+    #[test]
+    fn mermaid_uses_positional_ids_and_escapes_labels() {
+        let mut graph = Graph::default();
+        graph.add_node("neutron1a", "split \"in\"");
+        graph.add_node("neutron1b", "lper");
+        graph.add_edge("neutron1a", "neutron1b", "sends \"x\"");
+        graph.add_edge("neutron1b", "neutron1a", "");
+        graph.add_edge("neutron1b", "neutron1unknown", "skipped");
+
+        assert_eq!(
+            graph.to_mermaid(),
+            "flowchart LR\n  \
+            n0[\"split #quot;in#quot;<br/>neutron1a\"]\n  \
+            n1[\"lper<br/>neutron1b\"]\n  \
+            n0 -->|\"sends #quot;x#quot;\"| n1\n  \
+            n1 --> n0\n"
+        );
+    }
+}
@@ -1,72 +1,197 @@
+use crate::address::{address_strings, parse_addresses, Address};
 use crate::authorization::create_execute_messages_for_authorization;
-use crate::cli::{PoolInfo, ProgramAction};
+use crate::batch::TxBatch;
+use crate::cli::{DiagramFormat, LibraryConfigCommand, PoolInfo, ProgramAction, TopologyFormat};
 use crate::config::Config;
+use crate::daemon::{run_tick_daemon, TickDaemonOptions};
+use crate::dao::{
+    build_bank_send_msg, execute_or_propose, print_cosmos_proposal, print_proposal, ContractMsg,
+};
+use crate::fees::{
+    create_authorizations_gas, ownership_transfers_gas, print_estimate, StepEstimate,
+};
+use crate::funds::{build_sweep_msgs, check_source_balances, format_coins, required_funds};
 use crate::helpers::{
-    approve_library, build_deploy_subroutine, build_withdraw_subroutine,
-    create_and_execute_authorization, create_base_account, create_output_accounts,
-    get_filtered_authorizations, instantiate_and_approve_astroport_libraries,
-    instantiate_authorization_and_processor, instantiate_splitter_library, transfer_ownership,
+    build_accept_ownership_msg, build_authorizations_update_msgs, build_deploy_subroutine,
+    build_mode_change_authorization, build_pool_subroutines, build_splitter_update_msg,
+    build_withdraw_subroutine, create_and_execute_authorization, create_base_account,
+    create_output_accounts, find_authorizations, get_filtered_authorizations,
+    instantiate_astroport_libraries, instantiate_authorization_and_processor,
+    instantiate_pool_contracts, instantiate_splitter_library, transfer_accounts_ownership,
+    transfer_ownership,
+};
+use crate::library_config::{build_config_update, check_lper_pool, print_config_diff};
+use crate::manifest::{
+    apply_pending_manifest, check_manifest_absent, create_manifest, load_manifest, manifest_path,
+    save_pending_manifest, ProgramManifest,
+};
+use crate::plan::{
+    check_plan_collisions, generate_salt_nonce, plan_pool, plan_program, print_plan,
+};
+use crate::processor::{
+    execution_result_name, find_execution_id, get_next_batch, get_processor_authorization,
+    is_failed_execution, tick_and_report, wait_for_execution,
 };
-use crate::wasm::execute_wasm_contract;
-use anyhow::Result;
-use valence_processor_utils;
+use crate::program::{discover_program, get_library_processor, ProgramTopology};
+use crate::program_spec::{validate_mode_spec, AuthorizationModeSpec, ProgramSpec};
+use crate::proposal::render_program_proposal;
+use crate::scheduler::{
+    load_schedule, next_run_after, update_schedule, ScheduleTrigger, ScheduledExecution, SyncWindow,
+};
+use crate::status::{
+    classify_ownership, get_lp_token_balance, ownership_state, print_approved_libraries,
+    print_authorizations, print_balances, print_lp_positions, print_ownership,
+    print_processor_queue, OwnershipStatus,
+};
+use crate::wasm::{
+    execute_wasm_contract, execute_wasm_contract_with_tx, get_balances, get_latest_block,
+    get_library_config, get_ownership, query_wasm_smart,
+};
+use anyhow::{anyhow, Context, Result};
+use chrono::{TimeZone, Utc};
+use cw_utils::Expiration;
+use std::fs;
+use valence_authorization_utils::authorization::{AuthorizationInfo, Priority};
+use valence_authorization_utils::msg::{Mint, PermissionedMsg};
+
+#[allow(clippy::too_many_arguments)]
+pub fn create_program(
+    label_prefix: &str,
+    pools: &[PoolInfo],
+    per_pool_authorizations: bool,
+    program_spec: &ProgramSpec,
+    salt: Option<&str>,
+    dry_run: bool,
+    estimate: bool,
+    config: &Config,
+) -> Result<()> {
+    // Every contract is created with instantiate2, so all addresses are known up front
+    let salt_nonce = salt.map_or_else(generate_salt_nonce, String::from);
+    let plan = plan_program(label_prefix, pools.len(), &salt_nonce, config)?;
+    if dry_run {
+        print_plan(&plan);
+        return Ok(());
+    }
+    check_plan_collisions(&plan, config)?;
+    check_manifest_absent(&manifest_path(label_prefix, Some(&salt_nonce)))?;
+    println!(
+        "Deploying with salt {}, pass --salt {} to reproduce the addresses",
+        salt_nonce, salt_nonce
+    );
 
-pub fn create_program(label_prefix: &String, pools: &Vec<PoolInfo>, config: &Config) -> Result<()> {
     println!("Creating program with label {} ...", label_prefix);
 
+    let mut batch = TxBatch::new(config);
+
     // Deploy authorization and processor
     let (authorization_address, processor_address) =
-        instantiate_authorization_and_processor(&config)?;
+        instantiate_authorization_and_processor(config, &mut batch, &plan)?;
 
-    // Create input account
-    let input_account = create_base_account(config)?;
+    // Create accounts with their libraries approved at instantiation
+    let input_account = create_base_account(
+        config,
+        &mut batch,
+        &plan.input_account,
+        vec![plan.splitter_library.address.clone()],
+    )?;
     println!("Input Account Address: {}", input_account);
 
     let (split_output_accounts, liquidity_output_accounts, withdrawal_accounts) =
-        create_output_accounts(&config, &pools)?;
+        create_output_accounts(config, &mut batch, &plan)?;
 
     // Instantiate splitter library
-    let split_lib_address = instantiate_splitter_library(
-        config,
-        pools,
-        &input_account,
-        &split_output_accounts,
-        &processor_address,
-    )?;
+    let split_lib_address = instantiate_splitter_library(config, &mut batch, pools, &plan)?;
+
+    // Instantiate Astroport LPer and Astroport Withdrawal libraries per pool
+    let (astroport_lper_lib_addresses, astroport_withdraw_lib_addresses) =
+        instantiate_astroport_libraries(config, &mut batch, pools, &plan)?;
 
-    // Approve splitter library for input and output accounts
-    approve_library(config, &input_account, &split_lib_address)?;
-    for account in &split_output_accounts {
-        approve_library(config, account, &split_lib_address)?;
+    if estimate {
+        let (txs, gas) = batch.estimate();
+        // The program-wide deploy and withdraw authorizations, and both per pool if asked for
+        let authorizations = 2 + if per_pool_authorizations {
+            2 * pools.len()
+        } else {
+            0
+        };
+        // Authorization contract, input account and the three output accounts of every pool
+        let ownership_transfers = 2 + 3 * pools.len();
+        return print_estimate(
+            config,
+            &[
+                StepEstimate {
+                    step: "Instantiate contracts".to_string(),
+                    msgs: batch.msgs_count(),
+                    txs,
+                    gas,
+                    simulated: true,
+                },
+                StepEstimate {
+                    step: "Create authorizations".to_string(),
+                    msgs: 1,
+                    txs: 1,
+                    gas: create_authorizations_gas(authorizations),
+                    simulated: false,
+                },
+                StepEstimate {
+                    step: "Transfer ownership".to_string(),
+                    msgs: ownership_transfers,
+                    txs: ownership_transfers.div_ceil(config.max_msgs_per_tx),
+                    gas: ownership_transfers_gas(ownership_transfers),
+                    simulated: false,
+                },
+            ],
+        );
     }
 
-    // Instantiate Astroport LPer and Astroport Withdrawal libraries and approve them per pool
-    let (astroport_lper_lib_addresses, astroport_withdraw_lib_addresses) =
-        instantiate_and_approve_astroport_libraries(
-            &config,
-            &pools,
-            &split_output_accounts,
-            &liquidity_output_accounts,
-            &processor_address,
-        )?;
+    batch.broadcast()?;
+
+    // Save the program addresses before creating authorizations so they survive a failed run
+    let prefix = &config.bech32_prefix;
+    let manifest_path = create_manifest(&ProgramManifest {
+        label_prefix: label_prefix.to_string(),
+        authorization_address: Address::parse(&authorization_address, prefix)?,
+        processor_address: Address::parse(&processor_address, prefix)?,
+        input_account: Address::parse(&input_account, prefix)?,
+        split_output_accounts: parse_addresses(&split_output_accounts, prefix)?,
+        liquidity_output_accounts: parse_addresses(&liquidity_output_accounts, prefix)?,
+        withdrawal_accounts: parse_addresses(&withdrawal_accounts, prefix)?,
+        splitter_library: Address::parse(&split_lib_address, prefix)?,
+        astroport_lper_libraries: parse_addresses(&astroport_lper_lib_addresses, prefix)?,
+        astroport_withdraw_libraries: parse_addresses(&astroport_withdraw_lib_addresses, prefix)?,
+        pools: pools.to_vec(),
+        salt: Some(salt_nonce),
+    })?;
+    println!("Program manifest saved to {}", manifest_path);
 
     // Create deployment subroutines
     let deploy_subroutine =
         build_deploy_subroutine(&split_lib_address, &astroport_lper_lib_addresses);
     let withdraw_subroutine = build_withdraw_subroutine(&astroport_withdraw_lib_addresses);
+    let pool_subroutines = if per_pool_authorizations {
+        build_pool_subroutines(
+            pools,
+            &astroport_lper_lib_addresses,
+            &astroport_withdraw_lib_addresses,
+        )
+    } else {
+        vec![]
+    };
 
     // Create Authorization Messages and Execute
     create_and_execute_authorization(
         &authorization_address,
         deploy_subroutine,
         withdraw_subroutine,
-        &config,
+        pool_subroutines,
+        &program_spec.authorizations,
+        config,
         label_prefix,
     )?;
 
     // Transfer Ownership Athorization Contract and of Valence Accounts
     transfer_ownership(
-        &config,
+        config,
         &authorization_address,
         &input_account,
         &split_output_accounts,
@@ -81,14 +206,24 @@ pub fn create_program(label_prefix: &String, pools: &Vec<PoolInfo>, config: &Con
 pub fn execute_program(
     auth_contract_address: &str,
     action: ProgramAction,
+    pool: Option<&str>,
+    wait: bool,
+    max_iterations: u32,
     config: &Config,
 ) -> Result<()> {
     println!(
         "Executing program for contract {} ...",
         auth_contract_address
     );
+    if let (ProgramAction::Deploy, Some(pool)) = (&action, pool) {
+        println!(
+            "Warning: the deploy authorization of pool {} only provides liquidity from its split output account, run the program-wide deploy first if the funds were not split yet",
+            pool
+        );
+    }
+
     // Get the filtered authorizations based on action type
-    let authorizations = get_filtered_authorizations(auth_contract_address, action, config)?;
+    let authorizations = get_filtered_authorizations(auth_contract_address, action, pool, config)?;
 
     // Process each authorization and create messages
     for authorization in authorizations {
@@ -103,31 +238,1081 @@ pub fn execute_program(
             },
         );
 
-        // Execute contract call
-        execute_wasm_contract(
+        if !wait {
+            // Execute contract call
+            execute_wasm_contract(
+                auth_contract_address,
+                &serde_json::to_string(&send_msg)?,
+                config,
+            )?;
+            continue;
+        }
+
+        let tx_data = execute_wasm_contract_with_tx(
             auth_contract_address,
             &serde_json::to_string(&send_msg)?,
             config,
         )?;
+        let execution_id = find_execution_id(
+            config,
+            auth_contract_address,
+            &tx_data,
+            &authorization.label,
+        )?;
+        println!(
+            "Authorization {} enqueued as execution {}",
+            authorization.label, execution_id
+        );
+
+        let processor_address = authorization
+            .subroutine
+            .atomic
+            .functions
+            .iter()
+            .find_map(|function| function.contract_address())
+            .map(|library_address| get_library_processor(config, &library_address))
+            .transpose()?
+            .flatten()
+            .ok_or_else(|| {
+                anyhow!(
+                    "Processor of authorization {} not found",
+                    authorization.label
+                )
+            })?;
+
+        let execution_result = wait_for_execution(
+            config,
+            &processor_address,
+            auth_contract_address,
+            execution_id,
+            max_iterations,
+        )?;
+
+        if is_failed_execution(&execution_result) {
+            return Err(anyhow!(
+                "Execution {} of {} failed: {}",
+                execution_id,
+                authorization.label,
+                execution_result
+            ));
+        }
+        println!(
+            "Execution {} of {} completed: {}",
+            execution_id,
+            authorization.label,
+            execution_result_name(&execution_result)
+        );
+    }
+
+    Ok(())
+}
+
+pub fn tick_processor(
+    processor_contract_address: &str,
+    until_empty: bool,
+    max_iterations: u32,
+    config: &Config,
+) -> Result<()> {
+    let auth_contract_address = get_processor_authorization(config, processor_contract_address)?;
+
+    if !until_empty {
+        println!(
+            "Ticking the processor on address {} ...",
+            processor_contract_address
+        );
+        tick_and_report(config, processor_contract_address, &auth_contract_address)?;
+        return Ok(());
+    }
+
+    for iteration in 1..=max_iterations {
+        if get_next_batch(config, processor_contract_address)?.is_none() {
+            println!("Processor queue is empty after {} tick(s)", iteration - 1);
+            return Ok(());
+        }
+
+        println!(
+            "Ticking the processor on address {} ({}/{}) ...",
+            processor_contract_address, iteration, max_iterations
+        );
+        tick_and_report(config, processor_contract_address, &auth_contract_address)?;
+    }
+
+    if get_next_batch(config, processor_contract_address)?.is_some() {
+        return Err(anyhow!(
+            "Processor queue is not empty after {} ticks",
+            max_iterations
+        ));
+    }
+
+    println!("Processor queue is empty");
+    Ok(())
+}
+
+pub fn tick_daemon(options: &TickDaemonOptions, config: &Config) -> Result<()> {
+    run_tick_daemon(options, config)
+}
+
+pub fn processor_queue(processor_contract_address: &str, config: &Config) -> Result<()> {
+    let auth_contract_address = get_processor_authorization(config, processor_contract_address)?;
+    print_processor_queue(
+        config,
+        &auth_contract_address,
+        Some(processor_contract_address),
+    )
+}
+
+pub fn add_authorizations(
+    auth_contract_address: &str,
+    authorizations_file: &str,
+    as_proposal: bool,
+    config: &Config,
+) -> Result<()> {
+    let content = fs::read_to_string(authorizations_file)
+        .with_context(|| format!("Failed to read {}", authorizations_file))?;
+    let authorizations: Vec<AuthorizationInfo> =
+        serde_json::from_str(&content).with_context(|| {
+            format!(
+                "Failed to parse authorizations from {}",
+                authorizations_file
+            )
+        })?;
+
+    let labels: Vec<String> = authorizations
+        .iter()
+        .map(|authorization| authorization.label.clone())
+        .collect();
+    println!("Adding authorizations {:?} ...", labels);
+
+    let create_authorizations_msg = ContractMsg::new(
+        auth_contract_address,
+        &valence_authorization_utils::msg::ExecuteMsg::PermissionedAction(
+            PermissionedMsg::CreateAuthorizations { authorizations },
+        ),
+    )?;
+
+    execute_or_propose(
+        "Add authorizations",
+        &format!(
+            "Create authorizations {} on {}",
+            labels.join(", "),
+            auth_contract_address
+        ),
+        &[create_authorizations_msg],
+        as_proposal,
+        config,
+    )
+}
+
+pub fn set_authorization_enabled(
+    auth_contract_address: &str,
+    label: &str,
+    enabled: bool,
+    as_proposal: bool,
+    config: &Config,
+) -> Result<()> {
+    let (permissioned_msg, action) = if enabled {
+        (
+            PermissionedMsg::EnableAuthorization {
+                label: label.to_string(),
+            },
+            "Enable",
+        )
+    } else {
+        (
+            PermissionedMsg::DisableAuthorization {
+                label: label.to_string(),
+            },
+            "Disable",
+        )
+    };
+    println!("{} authorization {} ...", action, label);
+
+    let msg = ContractMsg::new(
+        auth_contract_address,
+        &valence_authorization_utils::msg::ExecuteMsg::PermissionedAction(permissioned_msg),
+    )?;
+
+    execute_or_propose(
+        &format!("{} authorization {}", action, label),
+        &format!(
+            "{} authorization {} on {}",
+            action, label, auth_contract_address
+        ),
+        &[msg],
+        as_proposal,
+        config,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn modify_authorization(
+    auth_contract_address: &str,
+    label: &str,
+    not_before: Option<Expiration>,
+    expiration: Option<Expiration>,
+    max_concurrent_executions: Option<u64>,
+    priority: Option<Priority>,
+    mints: Vec<Mint>,
+    mode: Option<AuthorizationModeSpec>,
+    as_proposal: bool,
+    config: &Config,
+) -> Result<()> {
+    println!("Modifying authorization {} ...", label);
+
+    let mut msgs = Vec::new();
+    // label the modifications apply to, which changes when the authorization is recreated
+    let mut target_label = label.to_string();
+    let mut expiration = expiration;
+    if let Some(mode) = mode {
+        validate_mode_spec(label, &mode)?;
+        let (authorization, current_expiration) =
+            build_mode_change_authorization(config, auth_contract_address, label, &mode)?;
+        println!(
+            "Authorization {} is disabled and recreated as {} with the new mode",
+            label, authorization.label
+        );
+
+        msgs.push(ContractMsg::new(
+            auth_contract_address,
+            &valence_authorization_utils::msg::ExecuteMsg::PermissionedAction(
+                PermissionedMsg::DisableAuthorization {
+                    label: label.to_string(),
+                },
+            ),
+        )?);
+        target_label = authorization.label.clone();
+        msgs.push(ContractMsg::new(
+            auth_contract_address,
+            &valence_authorization_utils::msg::ExecuteMsg::PermissionedAction(
+                PermissionedMsg::CreateAuthorizations {
+                    authorizations: vec![authorization],
+                },
+            ),
+        )?);
+        // the new authorization keeps the expiration unless another one is requested
+        if expiration.is_none() && current_expiration != (Expiration::Never {}) {
+            expiration = Some(current_expiration);
+        }
+    }
+
+    // The modifications and mints below apply to the recreated authorization after a mode change
+    if not_before.is_some()
+        || expiration.is_some()
+        || max_concurrent_executions.is_some()
+        || priority.is_some()
+    {
+        msgs.push(ContractMsg::new(
+            auth_contract_address,
+            &valence_authorization_utils::msg::ExecuteMsg::PermissionedAction(
+                PermissionedMsg::ModifyAuthorization {
+                    label: target_label.clone(),
+                    not_before,
+                    expiration,
+                    max_concurrent_executions,
+                    priority,
+                },
+            ),
+        )?);
+    }
+
+    // Permissions of a permissioned authorization are held as tokens, so granting
+    // new addresses (or more calls) is done by minting them
+    if !mints.is_empty() {
+        msgs.push(ContractMsg::new(
+            auth_contract_address,
+            &valence_authorization_utils::msg::ExecuteMsg::PermissionedAction(
+                PermissionedMsg::MintAuthorizations {
+                    label: target_label,
+                    mints,
+                },
+            ),
+        )?);
+    }
+
+    if msgs.is_empty() {
+        return Err(anyhow!("Nothing to modify for authorization {}", label));
+    }
+
+    execute_or_propose(
+        &format!("Modify authorization {}", label),
+        &format!(
+            "Modify authorization {} on {}",
+            label, auth_contract_address
+        ),
+        &msgs,
+        as_proposal,
+        config,
+    )
+}
+
+pub fn program_status(
+    auth_contract_address: &str,
+    manifest_path: Option<&str>,
+    config: &Config,
+) -> Result<()> {
+    println!("Collecting status of program {} ...", auth_contract_address);
+    let manifest = manifest_path
+        .map(|path| load_manifest(path, &config.bech32_prefix))
+        .transpose()?;
+    let (topology, authorizations) =
+        discover_program(config, auth_contract_address, manifest.as_ref())?;
+    let accounts = topology.accounts();
+
+    print_authorizations(&authorizations);
+    print_processor_queue(
+        config,
+        auth_contract_address,
+        topology.processor_address.as_deref(),
+    )?;
+    print_ownership(config, auth_contract_address, &accounts)?;
+    print_approved_libraries(config, &accounts)?;
+    print_balances(config, &topology)?;
+    print_lp_positions(config, &topology.pools)?;
+
+    Ok(())
+}
+
+pub fn inspect_program(
+    auth_contract_address: &str,
+    manifest_path: Option<&str>,
+    format: &TopologyFormat,
+    output: Option<&str>,
+    config: &Config,
+) -> Result<()> {
+    let manifest = manifest_path
+        .map(|path| load_manifest(path, &config.bech32_prefix))
+        .transpose()?;
+    let (topology, _) = discover_program(config, auth_contract_address, manifest.as_ref())?;
+
+    let rendered = match format {
+        TopologyFormat::Json => serde_json::to_string_pretty(&topology)?,
+        TopologyFormat::Dot => topology.graph().to_dot(auth_contract_address),
+    };
+
+    write_output(&rendered, output, "topology")
+}
+
+pub fn program_diagram(
+    auth_contract_address: Option<&str>,
+    manifest_path: Option<&str>,
+    format: &DiagramFormat,
+    output: Option<&str>,
+    config: &Config,
+) -> Result<()> {
+    let manifest = manifest_path
+        .map(|path| load_manifest(path, &config.bech32_prefix))
+        .transpose()?;
+    let graph = match (auth_contract_address, &manifest) {
+        (Some(auth_contract_address), _) => {
+            let (topology, authorizations) =
+                discover_program(config, auth_contract_address, manifest.as_ref())?;
+            topology.flow_graph(&authorizations)
+        }
+        // create-program points the withdrawers to the DAO committee
+        (None, Some(manifest)) => {
+            ProgramTopology::from_manifest(manifest, &config.neutron_dao_committee_address)
+                .flow_graph(&[])
+        }
+        (None, None) => {
+            return Err(anyhow!(
+                "Either an authorization contract address or a manifest is required"
+            ))
+        }
+    };
+
+    let rendered = match format {
+        DiagramFormat::Dot => graph.to_dot("program"),
+        DiagramFormat::Mermaid => graph.to_mermaid(),
+    };
+    write_output(&rendered, output, "diagram")
+}
+
+pub fn program_proposal(manifest_path: &str, output: Option<&str>, config: &Config) -> Result<()> {
+    let manifest = load_manifest(manifest_path, &config.bech32_prefix)?;
+    let proposal = render_program_proposal(config, &manifest)?;
+    write_output(&proposal, output, "proposal")
+}
+
+/// Writes a rendered topology, diagram or proposal to the file, or prints it
+fn write_output(rendered: &str, output: Option<&str>, what: &str) -> Result<()> {
+    match output {
+        Some(path) => {
+            fs::write(path, rendered)
+                .with_context(|| format!("Failed to write {} to {}", what, path))?;
+            println!("Saved {} to {}", what, path);
+        }
+        None => println!("{}", rendered),
+    }
+
+    Ok(())
+}
+
+pub fn verify_ownership(
+    auth_contract_address: &str,
+    manifest_path: Option<&str>,
+    reissue_expired: bool,
+    config: &Config,
+) -> Result<()> {
+    println!(
+        "Verifying ownership of program {} ...",
+        auth_contract_address
+    );
+    let manifest = manifest_path
+        .map(|path| load_manifest(path, &config.bech32_prefix))
+        .transpose()?;
+    let (topology, _) = discover_program(config, auth_contract_address, manifest.as_ref())?;
+
+    let mut contracts = vec![auth_contract_address.to_string()];
+    contracts.extend(topology.accounts());
+
+    let block = get_latest_block(config)?;
+    let mut statuses = Vec::new();
+    for contract_address in contracts {
+        let ownership = get_ownership(config, &contract_address)?;
+        let status = classify_ownership(
+            &ownership,
+            &config.neutron_dao_committee_address,
+            &config.tool_operator_address,
+            block,
+        );
+        println!(
+            "{} | {} | {}",
+            contract_address,
+            status.name(),
+            ownership_state(&ownership)
+        );
+        statuses.push((contract_address, status));
+    }
+
+    let with_status = |status: OwnershipStatus| -> Vec<String> {
+        statuses
+            .iter()
+            .filter(|(_, contract_status)| *contract_status == status)
+            .map(|(contract_address, _)| contract_address.clone())
+            .collect()
+    };
+    let pending = with_status(OwnershipStatus::Pending);
+    let expired = with_status(OwnershipStatus::Expired);
+    let not_transferred = with_status(OwnershipStatus::NotTransferred);
+    let foreign = with_status(OwnershipStatus::Foreign);
+
+    println!(
+        "{} contract(s) accepted and {} pending acceptance by {}, {} expired, {} not transferred, {} foreign",
+        with_status(OwnershipStatus::Accepted).len(),
+        pending.len(),
+        config.neutron_dao_committee_address,
+        expired.len(),
+        not_transferred.len(),
+        foreign.len()
+    );
+    if !foreign.is_empty() {
+        println!(
+            "Owned by or pending to another address: {}",
+            foreign.join(", ")
+        );
+    }
+
+    // Expired transfers can't be accepted anymore, the operator has to transfer them again
+    let retransfer: Vec<String> = expired.iter().chain(&not_transferred).cloned().collect();
+    if !retransfer.is_empty() {
+        if reissue_expired {
+            println!(
+                "Transferring ownership of {} contract(s) to {} ...",
+                retransfer.len(),
+                config.neutron_dao_committee_address
+            );
+            let mut batch = TxBatch::new(config);
+            transfer_accounts_ownership(
+                config,
+                &mut batch,
+                &retransfer,
+                &config.neutron_dao_committee_address,
+            )?;
+            batch.broadcast()?;
+            println!("Run verify-ownership again to include them in the accept proposal");
+        } else {
+            println!(
+                "Ownership of {} is not transferred or the transfer expired, re-run with --reissue-expired to transfer it again",
+                retransfer.join(", ")
+            );
+        }
+    }
+
+    if !pending.is_empty() {
+        let accept_msgs = pending
+            .iter()
+            .map(|contract_address| build_accept_ownership_msg(contract_address))
+            .collect::<Result<Vec<_>>>()?;
+
+        println!("Accept ownership proposal:");
+        print_proposal(
+            "Accept ownership of program contracts",
+            &format!(
+                "Accept ownership of the authorization contract and Valence accounts of program {}",
+                auth_contract_address
+            ),
+            &accept_msgs,
+        )?;
     }
 
     Ok(())
 }
 
-pub fn tick_processor(processor_contract_address: &String, config: &Config) -> Result<()> {
+pub fn fund_program(
+    manifest_path: &str,
+    as_proposal: bool,
+    treasury_address: Option<&str>,
+    config: &Config,
+) -> Result<()> {
+    let manifest = load_manifest(manifest_path, &config.bech32_prefix)?;
+    let required = required_funds(&manifest.pools);
+    let source = match (as_proposal, treasury_address) {
+        (true, Some(treasury_address)) => treasury_address,
+        (true, None) => config.neutron_dao_committee_address.as_str(),
+        (false, _) => config.tool_operator_address.as_str(),
+    };
     println!(
-        "Ticking the processor on address {} ...",
-        processor_contract_address
+        "Funding input account {} of program {} with {} from {} ...",
+        manifest.input_account,
+        manifest.label_prefix,
+        format_coins(&required),
+        source
     );
-    let tick_msg = valence_processor_utils::msg::ExecuteMsg::PermissionlessAction(
-        valence_processor_utils::msg::PermissionlessMsg::Tick {},
+    check_source_balances(config, source, &required)?;
+
+    if as_proposal {
+        return print_cosmos_proposal(
+            &format!("Fund program {}", manifest.label_prefix),
+            &format!(
+                "Send {} from the treasury to the input account {} of program {}",
+                format_coins(&required),
+                manifest.input_account,
+                manifest.label_prefix
+            ),
+            &[build_bank_send_msg(&manifest.input_account, &required)],
+        );
+    }
+
+    let mut batch = TxBatch::new(config);
+    batch.send(&manifest.input_account, &required)?;
+    batch.broadcast()?;
+
+    println!("Input account {} funded", manifest.input_account);
+    Ok(())
+}
+
+pub fn sweep(
+    auth_contract_address: &str,
+    manifest_path: Option<&str>,
+    recipient: &str,
+    as_proposal: bool,
+    config: &Config,
+) -> Result<()> {
+    println!(
+        "Sweeping the accounts of program {} to {} ...",
+        auth_contract_address, recipient
+    );
+    let manifest = manifest_path
+        .map(|path| load_manifest(path, &config.bech32_prefix))
+        .transpose()?;
+    let (topology, _) = discover_program(config, auth_contract_address, manifest.as_ref())?;
+
+    let msgs = build_sweep_msgs(config, &topology.accounts(), recipient)?;
+    if msgs.is_empty() {
+        println!("All program accounts are empty, nothing to sweep");
+        return Ok(());
+    }
+
+    execute_or_propose(
+        "Sweep program accounts",
+        &format!(
+            "Send the funds left in the Valence accounts of program {} to {}",
+            auth_contract_address, recipient
+        ),
+        &msgs,
+        as_proposal,
+        config,
+    )
+}
+
+pub fn add_pool(
+    manifest_path: &str,
+    pool: &PoolInfo,
+    per_pool_authorizations: bool,
+    program_spec: &ProgramSpec,
+    as_proposal: bool,
+    config: &Config,
+) -> Result<()> {
+    check_committee_execution(as_proposal, config)?;
+    let mut manifest = load_manifest(manifest_path, &config.bech32_prefix)?;
+    if let Some(existing) = manifest
+        .pools
+        .iter()
+        .find(|existing| existing.address == pool.address || existing.label_id() == pool.label_id())
+    {
+        return Err(anyhow!(
+            "Pool {} ({}) of program {} already uses the address or name of pool {}",
+            existing.label_id(),
+            existing.address,
+            manifest.label_prefix,
+            pool.label_id()
+        ));
+    }
+
+    // Manifests of older deployments have no salt, the new contracts only need unused addresses
+    let salt_nonce = manifest.salt.clone().unwrap_or_else(generate_salt_nonce);
+    let plan = plan_pool(
+        &manifest.label_prefix,
+        manifest.pools.len(),
+        &salt_nonce,
+        config,
+    )?;
+    println!(
+        "Adding pool {} to program {} as pool {} ...",
+        pool.label_id(),
+        manifest.label_prefix,
+        plan.index
+    );
+
+    let mut batch = TxBatch::new(config);
+    instantiate_pool_contracts(
+        config,
+        &mut batch,
+        pool,
+        &plan,
+        &manifest.splitter_library,
+        &manifest.processor_address.to_string(),
+    )?;
+    batch.broadcast()?;
+
+    let accounts = vec![
+        plan.split_output_account.address.clone(),
+        plan.liquidity_output_account.address.clone(),
+        plan.withdrawal_account.address.clone(),
+    ];
+    transfer_accounts_ownership(
+        config,
+        &mut batch,
+        &accounts,
+        &config.neutron_dao_committee_address,
+    )?;
+    batch.broadcast()?;
+
+    manifest.pools.push(pool.clone());
+    manifest.split_output_accounts.push(Address::parse(
+        &plan.split_output_account.address,
+        &config.bech32_prefix,
+    )?);
+    manifest.liquidity_output_accounts.push(Address::parse(
+        &plan.liquidity_output_account.address,
+        &config.bech32_prefix,
+    )?);
+    manifest.withdrawal_accounts.push(Address::parse(
+        &plan.withdrawal_account.address,
+        &config.bech32_prefix,
+    )?);
+    manifest.astroport_lper_libraries.push(Address::parse(
+        &plan.astroport_lper_library.address,
+        &config.bech32_prefix,
+    )?);
+    manifest.astroport_withdraw_libraries.push(Address::parse(
+        &plan.astroport_withdraw_library.address,
+        &config.bech32_prefix,
+    )?);
+    // The new contracts exist now, record them before anything else can fail
+    let pending_path = save_pending_manifest(manifest_path, &manifest)?;
+
+    let pool_subroutines = if per_pool_authorizations {
+        build_pool_subroutines(
+            std::slice::from_ref(pool),
+            std::slice::from_ref(&plan.astroport_lper_library.address),
+            std::slice::from_ref(&plan.astroport_withdraw_library.address),
+        )
+    } else {
+        vec![]
+    };
+    let mut msgs = vec![build_splitter_update_msg(
+        &manifest.splitter_library,
+        &manifest.pools,
+        &address_strings(&manifest.split_output_accounts),
+    )?];
+    msgs.extend(build_authorizations_update_msgs(
+        config,
+        &manifest.authorization_address,
+        &manifest.label_prefix,
+        &manifest.splitter_library,
+        &address_strings(&manifest.astroport_lper_libraries),
+        &address_strings(&manifest.astroport_withdraw_libraries),
+        pool_subroutines,
+        None,
+        &program_spec.authorizations,
+    )?);
+    msgs.extend(
+        accounts
+            .iter()
+            .map(|account| build_accept_ownership_msg(account))
+            .collect::<Result<Vec<_>>>()?,
+    );
+
+    execute_or_propose(
+        &format!("Add pool {} to program {}", pool.label_id(), manifest.label_prefix),
+        &format!(
+            "Split {}{} and {}{} to the new pool {}, replace the deploy and withdraw authorizations and accept ownership of the new accounts",
+            pool.amount_a, pool.denom_a, pool.amount_b, pool.denom_b, pool.address
+        ),
+        &msgs,
+        as_proposal,
+        config,
+    )?;
+
+    finish_manifest_update(manifest_path, &pending_path, as_proposal)
+}
+
+pub fn remove_pool(
+    manifest_path: &str,
+    pool: &str,
+    skip_withdraw: bool,
+    max_iterations: u32,
+    program_spec: &ProgramSpec,
+    as_proposal: bool,
+    config: &Config,
+) -> Result<()> {
+    check_committee_execution(as_proposal, config)?;
+    let mut manifest = load_manifest(manifest_path, &config.bech32_prefix)?;
+    let index = manifest
+        .pools
+        .iter()
+        .position(|existing| existing.label_id() == pool || existing.address.as_str() == pool)
+        .ok_or_else(|| {
+            anyhow!(
+                "Pool {} is not part of program {}",
+                pool,
+                manifest.label_prefix
+            )
+        })?;
+    let pool_id = manifest.pools[index].label_id().to_string();
+
+    if skip_withdraw {
+        check_pool_empty(config, &manifest, index)?;
+    } else {
+        let has_pool_withdraw = !find_authorizations(
+            &manifest.authorization_address,
+            ProgramAction::Withdraw,
+            Some(&pool_id),
+            config,
+        )?
+        .is_empty();
+        if !has_pool_withdraw {
+            println!(
+                "Pool {} has no per-pool withdraw authorization, withdrawing every pool of program {} with the program-wide withdraw ...",
+                pool_id, manifest.label_prefix
+            );
+        }
+        execute_program(
+            &manifest.authorization_address,
+            ProgramAction::Withdraw,
+            has_pool_withdraw.then_some(pool_id.as_str()),
+            true,
+            max_iterations,
+            config,
+        )
+        .context(
+            "Failed to withdraw the pool liquidity, pass --skip-withdraw if the pool holds none",
+        )?;
+    }
+    println!(
+        "Removing pool {} from program {} ...",
+        pool_id, manifest.label_prefix
     );
 
-    execute_wasm_contract(
-        &processor_contract_address,
-        &serde_json::to_string(&tick_msg)?,
+    manifest.pools.remove(index);
+    manifest.split_output_accounts.remove(index);
+    manifest.liquidity_output_accounts.remove(index);
+    manifest.withdrawal_accounts.remove(index);
+    manifest.astroport_lper_libraries.remove(index);
+    manifest.astroport_withdraw_libraries.remove(index);
+
+    let mut msgs = vec![build_splitter_update_msg(
+        &manifest.splitter_library,
+        &manifest.pools,
+        &address_strings(&manifest.split_output_accounts),
+    )?];
+    msgs.extend(build_authorizations_update_msgs(
+        config,
+        &manifest.authorization_address,
+        &manifest.label_prefix,
+        &manifest.splitter_library,
+        &address_strings(&manifest.astroport_lper_libraries),
+        &address_strings(&manifest.astroport_withdraw_libraries),
+        vec![],
+        Some(&pool_id),
+        &program_spec.authorizations,
+    )?);
+
+    let pending_path = save_pending_manifest(manifest_path, &manifest)?;
+    execute_or_propose(
+        &format!("Remove pool {} from program {}", pool_id, manifest.label_prefix),
+        &format!(
+            "Stop splitting funds to pool {} and replace the deploy and withdraw authorizations without it",
+            pool_id
+        ),
+        &msgs,
+        as_proposal,
         config,
     )?;
 
+    // The pool accounts stay owned by the DAO, leftovers can be recovered with sweep
+    finish_manifest_update(manifest_path, &pending_path, as_proposal)
+}
+
+/// Applies the pending manifest of an executed change, or leaves it for the operator
+/// to apply once the DAO has executed the proposal
+/// The splitter and the authorization contract belong to the DAO committee, so updating
+/// the pools of a program can only be executed directly when the operator is the committee
+fn check_committee_execution(as_proposal: bool, config: &Config) -> Result<()> {
+    if !as_proposal && config.tool_operator_address != config.neutron_dao_committee_address {
+        return Err(anyhow!(
+            "The program contracts are owned by the DAO committee {}, which the operator {} can't sign for, leave out --execute to print the proposal",
+            config.neutron_dao_committee_address,
+            config.tool_operator_address
+        ));
+    }
+
     Ok(())
 }
+
+/// Refuses to drop a pool from the program while its accounts still hold funds or LP tokens,
+/// which would otherwise be left behind unnoticed
+fn check_pool_empty(config: &Config, manifest: &ProgramManifest, index: usize) -> Result<()> {
+    let topology = ProgramTopology::from_manifest(manifest, &config.neutron_dao_committee_address);
+    let pool = &topology.pools[index];
+
+    let lp_amount = get_lp_token_balance(config, pool)?;
+    if lp_amount != "0" {
+        return Err(anyhow!(
+            "Liquidity output account {} still holds {} LP tokens of pool {}, withdraw them before removing the pool",
+            pool.liquidity_output_account,
+            lp_amount,
+            pool.pool_address
+        ));
+    }
+
+    let accounts = [
+        Some(&pool.split_output_account),
+        Some(&pool.liquidity_output_account),
+        pool.withdrawal_account.as_ref(),
+    ];
+    for account in accounts.into_iter().flatten() {
+        let balances: Vec<_> = get_balances(config, account)?
+            .into_iter()
+            .filter(|coin| !coin.amount.is_zero())
+            .collect();
+        if !balances.is_empty() {
+            return Err(anyhow!(
+                "Account {} of pool {} still holds {}, withdraw or sweep it before removing the pool",
+                account,
+                pool.pool_address,
+                format_coins(&balances)
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn finish_manifest_update(
+    manifest_path: &str,
+    pending_path: &str,
+    as_proposal: bool,
+) -> Result<()> {
+    if as_proposal {
+        println!(
+            "Updated program manifest saved to {}, replace {} with it once the DAO has executed the proposal",
+            pending_path, manifest_path
+        );
+        return Ok(());
+    }
+
+    apply_pending_manifest(manifest_path)?;
+    println!("Program manifest saved to {}", manifest_path);
+    Ok(())
+}
+
+pub fn update_library_config(
+    library: &LibraryConfigCommand,
+    as_proposal: bool,
+    config: &Config,
+) -> Result<()> {
+    let library_address = library.library_address();
+    let current = get_library_config(config, library_address)?;
+    if let LibraryConfigCommand::Lper {
+        pool_address: Some(pool_address),
+        ..
+    } = library
+    {
+        let pair = query_wasm_smart(config, pool_address, r#"{"pair":{}}"#)?;
+        check_lper_pool(&current, &pair, &config.bech32_prefix)?;
+    }
+    let (changes, msg) = build_config_update(library, &current)?;
+
+    if changes.is_empty() {
+        println!(
+            "The config of {} already matches, nothing to update",
+            library_address
+        );
+        return Ok(());
+    }
+    print_config_diff(library_address, &changes);
+
+    let fields: Vec<&str> = changes.iter().map(|change| change.field).collect();
+    execute_or_propose(
+        &format!("Update config of library {}", library_address),
+        &format!(
+            "Update {} of library {}",
+            fields.join(", "),
+            library_address
+        ),
+        &[msg],
+        as_proposal,
+        config,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn schedule_add(
+    schedule_file: &str,
+    auth_contract_address: &str,
+    action: ProgramAction,
+    pool: Option<&str>,
+    trigger: ScheduleTrigger,
+    wait: bool,
+    sync_window: Option<(u64, bool)>,
+    config: &Config,
+) -> Result<()> {
+    let now = Utc::now().timestamp();
+    let next_run = next_run_after(&trigger, now)?
+        .ok_or_else(|| anyhow!("Scheduled time {:?} is in the past", trigger))?;
+
+    // Match the on-chain time constraints of the authorizations to the schedule
+    let mut window = None;
+    if let (Some((window_secs, as_proposal)), ScheduleTrigger::At { timestamp }) =
+        (sync_window, &trigger)
+    {
+        let sync_window = SyncWindow {
+            not_before: *timestamp,
+            expiration: *timestamp + window_secs as i64,
+        };
+        let authorizations =
+            get_filtered_authorizations(auth_contract_address, action.clone(), pool, config)?;
+        let msgs = authorizations
+            .iter()
+            .map(|authorization| {
+                ContractMsg::new(
+                    auth_contract_address,
+                    &valence_authorization_utils::msg::ExecuteMsg::PermissionedAction(
+                        PermissionedMsg::ModifyAuthorization {
+                            label: authorization.label.clone(),
+                            not_before: Some(sync_window.not_before()),
+                            expiration: Some(sync_window.expiration()),
+                            max_concurrent_executions: None,
+                            priority: None,
+                        },
+                    ),
+                )
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let labels: Vec<&str> = authorizations
+            .iter()
+            .map(|authorization| authorization.label.as_str())
+            .collect();
+        execute_or_propose(
+            &format!("Set the window of {}", labels.join(", ")),
+            &format!(
+                "Allow executing {} on {} from {} to {}",
+                labels.join(", "),
+                auth_contract_address,
+                format_timestamp(sync_window.not_before),
+                format_timestamp(sync_window.expiration)
+            ),
+            &msgs,
+            as_proposal,
+            config,
+        )?;
+        // The scheduler checks the window on chain before running, so a proposal that
+        // hasn't passed yet delays the run instead of failing it
+        window = Some(sync_window);
+    }
+
+    let id = update_schedule(schedule_file, |state| {
+        let id = state.next_id;
+        state.next_id += 1;
+        state.executions.push(ScheduledExecution {
+            id,
+            auth_contract_address: auth_contract_address.to_string(),
+            action,
+            pool: pool.map(String::from),
+            trigger,
+            wait,
+            next_run: Some(next_run),
+            last_run: None,
+            last_error: None,
+            failed_attempts: 0,
+            sync_window: window,
+        });
+        Ok(id)
+    })?;
+
+    println!(
+        "Scheduled execution {} to run next at {}",
+        id,
+        format_timestamp(next_run)
+    );
+    Ok(())
+}
+
+pub fn schedule_list(schedule_file: &str) -> Result<()> {
+    let state = load_schedule(schedule_file)?;
+    for execution in &state.executions {
+        println!(
+            "{} | {:?}{} on {} | {:?} | next run: {} | last run: {}{}",
+            execution.id,
+            execution.action,
+            execution
+                .pool
+                .as_ref()
+                .map_or(String::new(), |pool| format!(" of pool {}", pool)),
+            execution.auth_contract_address,
+            execution.trigger,
+            execution.next_run.map_or("-".to_string(), format_timestamp),
+            execution.last_run.map_or("-".to_string(), format_timestamp),
+            execution
+                .last_error
+                .as_ref()
+                .map_or(String::new(), |e| format!(
+                    " | last error ({} failed attempt(s)): {}",
+                    execution.failed_attempts, e
+                )),
+        );
+    }
+
+    Ok(())
+}
+
+pub fn schedule_remove(schedule_file: &str, id: u64) -> Result<()> {
+    update_schedule(schedule_file, |state| {
+        let executions_count = state.executions.len();
+        state.executions.retain(|execution| execution.id != id);
+        if state.executions.len() == executions_count {
+            return Err(anyhow!("Scheduled execution {} not found", id));
+        }
+        Ok(())
+    })?;
+    println!("Removed scheduled execution {}", id);
+    Ok(())
+}
+
+fn format_timestamp(timestamp: i64) -> String {
+    Utc.timestamp_opt(timestamp, 0)
+        .single()
+        .map_or(timestamp.to_string(), |date| date.to_rfc3339())
+}
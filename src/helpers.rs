@@ -1,24 +1,29 @@
-use crate::authorization::Authorization;
+use crate::address::{addr_canonicalize, addr_humanize};
+use crate::authorization::{
+    Authorization, PROVIDE_DOUBLE_SIDED_LIQUIDITY, SPLIT, WITHDRAW_LIQUIDITY,
+};
+use crate::batch::TxBatch;
 use crate::cli::{PoolInfo, ProgramAction};
-use crate::config::Config;
+use crate::config::{Config, SignerKind};
+use crate::dao::ContractMsg;
+use crate::plan::{addresses, PlannedContract, PoolPlan, ProgramPlan};
+use crate::program_spec::{AuthorizationModeSpec, AuthorizationSpec, AuthorizationsSpec};
 use crate::wasm::{
-    execute_wasm_contract, get_authorizations, get_code_hash, instantiate2_wasm_contract,
-    instantiate_wasm_contract,
+    execute_wasm_contract, get_authorizations, get_bech32_prefix, get_key_address, query_wasm_smart,
 };
-use anyhow::{Error, Result};
-use bech32::{encode, primitives::decode::CheckedHrpstring, Bech32, Hrp};
+use anyhow::{Context, Error, Result};
 use chrono::Utc;
-use cosmwasm_std::{instantiate2_address, Addr, CanonicalAddr, HexBinary};
-use cw_ownable;
+use cosmwasm_std::{instantiate2_address, HexBinary, Timestamp};
+use cw_utils::Expiration;
 use serde_json::Value;
 use std::collections::HashMap;
-use valence_account_utils::msg::{ExecuteMsg, InstantiateMsg};
+use valence_account_utils::msg::InstantiateMsg;
 use valence_astroport_lper::msg::{
     LibraryConfig as AstroLperLibraryConfig, LiquidityProviderConfig,
 };
 use valence_astroport_withdrawer::msg::LibraryConfig as AstroWithdrawerLibraryConfig;
 use valence_authorization_utils::{
-    authorization::{AuthorizationModeInfo, PermissionTypeInfo, Subroutine},
+    authorization::{AuthorizationInfo, Subroutine},
     authorization_message::{Message, MessageDetails, MessageType, ParamRestriction},
     builders::{AtomicFunctionBuilder, AtomicSubroutineBuilder, AuthorizationBuilder},
 };
@@ -27,39 +32,51 @@ use valence_library_utils::{
 };
 use valence_splitter_library::msg::LibraryConfig as SpliterLibraryConfig;
 
-const NEUTRON_BECH32_PREFIX: &str = "neutron";
 const DELIMITER: &str = "_";
 const DEPLOY: &str = "deploy";
 const WITHDRAW: &str = "withdraw";
 
-pub fn create_base_account(config: &Config) -> Result<String> {
+/// Creates a base account with its libraries already approved, since their addresses are planned
+pub fn create_base_account(
+    config: &Config,
+    batch: &mut TxBatch,
+    account: &PlannedContract,
+    approved_libraries: Vec<String>,
+) -> Result<String> {
     let acc_instantiate_msg = InstantiateMsg {
         admin: config.tool_operator_address.to_string(), // once the program is created we will transfer the ownership to dao comittee
-        approved_libraries: vec![],
+        approved_libraries,
     };
 
-    let contract_address = instantiate_wasm_contract(
-        config.base_account_code_id,
-        &serde_json::to_string(&acc_instantiate_msg)?,
-        config,
-        "base_account",
-    )?;
-
-    Ok(contract_address)
+    batch.instantiate2(account, &acc_instantiate_msg)
 }
 
 pub fn create_output_accounts(
     config: &Config,
-    pools: &Vec<PoolInfo>,
+    batch: &mut TxBatch,
+    plan: &ProgramPlan,
 ) -> Result<(Vec<String>, Vec<String>, Vec<String>)> {
     let mut split_output_accounts = Vec::new();
     let mut liquidity_output_accounts = Vec::new();
     let mut withdrawal_accounts = Vec::new();
 
-    for _ in 0..pools.len() {
-        split_output_accounts.push(create_base_account(config)?.to_string());
-        liquidity_output_accounts.push(create_base_account(config)?.to_string());
-        withdrawal_accounts.push(create_base_account(config)?.to_string());
+    for i in 0..plan.split_output_accounts.len() {
+        let (split_output_account, liquidity_output_account, withdrawal_account) =
+            create_pool_accounts(
+                config,
+                batch,
+                &plan.splitter_library.address,
+                [
+                    &plan.split_output_accounts[i],
+                    &plan.liquidity_output_accounts[i],
+                    &plan.withdrawal_accounts[i],
+                ],
+                &plan.astroport_lper_libraries[i].address,
+                &plan.astroport_withdraw_libraries[i].address,
+            )?;
+        split_output_accounts.push(split_output_account);
+        liquidity_output_accounts.push(liquidity_output_account);
+        withdrawal_accounts.push(withdrawal_account);
     }
 
     Ok((
@@ -69,16 +86,89 @@ pub fn create_output_accounts(
     ))
 }
 
-pub fn instantiate_splitter_library(
+/// Creates the split output, liquidity output and withdrawal accounts of a pool
+fn create_pool_accounts(
     config: &Config,
-    pools: &Vec<PoolInfo>,
-    input_addr: &String,
-    output_addrs: &Vec<String>,
-    processor_addr: &String,
-) -> Result<String> {
-    let splits: Vec<_> = pools
+    batch: &mut TxBatch,
+    splitter_library: &str,
+    [split_output_account, liquidity_output_account, withdrawal_account]: [&PlannedContract; 3],
+    lper_library: &str,
+    withdraw_library: &str,
+) -> Result<(String, String, String)> {
+    // The splitter fills the split output account, from which the LPer provides liquidity
+    let split_output_account = create_base_account(
+        config,
+        batch,
+        split_output_account,
+        vec![splitter_library.to_string(), lper_library.to_string()],
+    )?;
+    // The LPer deposits LP tokens to the liquidity output account, from which the withdrawer withdraws
+    let liquidity_output_account = create_base_account(
+        config,
+        batch,
+        liquidity_output_account,
+        vec![lper_library.to_string(), withdraw_library.to_string()],
+    )?;
+    let withdrawal_account = create_base_account(config, batch, withdrawal_account, vec![])?;
+
+    Ok((
+        split_output_account,
+        liquidity_output_account,
+        withdrawal_account,
+    ))
+}
+
+/// Creates the accounts and libraries of a pool added to a deployed program.
+/// The new accounts approve the existing splitter, so the accounts already deployed need no change.
+pub fn instantiate_pool_contracts(
+    config: &Config,
+    batch: &mut TxBatch,
+    pool: &PoolInfo,
+    plan: &PoolPlan,
+    splitter_library: &str,
+    processor_address: &String,
+) -> Result<()> {
+    create_pool_accounts(
+        config,
+        batch,
+        splitter_library,
+        [
+            &plan.split_output_account,
+            &plan.liquidity_output_account,
+            &plan.withdrawal_account,
+        ],
+        &plan.astroport_lper_library.address,
+        &plan.astroport_withdraw_library.address,
+    )?;
+    instantiate_astro_lper_library(
+        config,
+        batch,
+        pool,
+        &plan.astroport_lper_library,
+        &plan.split_output_account.address,
+        &plan.liquidity_output_account.address,
+        processor_address,
+    )?;
+    instantiate_astro_withdraw_library(
+        config,
+        batch,
+        pool,
+        &plan.astroport_withdraw_library,
+        &plan.liquidity_output_account.address,
+        processor_address,
+    )?;
+
+    Ok(())
+}
+
+/// Fixed amount splits of both pool assets into the split output account of every pool
+fn build_splits(
+    pools: &[PoolInfo],
+    split_output_accounts: &[String],
+) -> Vec<valence_splitter_library::msg::UncheckedSplitConfig> {
+    pools
         .iter()
-        .zip(output_addrs.iter())
+        .zip(split_output_accounts)
         .flat_map(|(pool, output_addr)| {
             vec![
                 valence_splitter_library::msg::UncheckedSplitConfig {
@@ -97,67 +187,83 @@ pub fn instantiate_splitter_library(
                 },
             ]
         })
-        .collect();
+        .collect()
+}
+
+/// Replaces the splits of the splitter library, which is owned by the DAO committee
+pub fn build_splitter_update_msg(
+    splitter_library: &str,
+    pools: &[PoolInfo],
+    split_output_accounts: &[String],
+) -> Result<ContractMsg> {
+    ContractMsg::new(
+        splitter_library,
+        &valence_library_utils::msg::ExecuteMsg::<
+            valence_splitter_library::msg::FunctionMsgs,
+            valence_splitter_library::msg::LibraryConfigUpdate,
+        >::UpdateConfig {
+            new_config: valence_splitter_library::msg::LibraryConfigUpdate {
+                input_addr: None,
+                splits: Some(build_splits(pools, split_output_accounts)),
+            },
+        },
+    )
+}
+
+pub fn instantiate_splitter_library(
+    config: &Config,
+    batch: &mut TxBatch,
+    pools: &[PoolInfo],
+    plan: &ProgramPlan,
+) -> Result<String> {
+    let splits = build_splits(pools, &addresses(&plan.split_output_accounts));
 
     let split_lib_instantiate_msg =
         valence_library_utils::msg::InstantiateMsg::<SpliterLibraryConfig> {
             owner: config.neutron_dao_committee_address.to_string(),
-            processor: processor_addr.to_string(),
+            processor: plan.processor.address.to_string(),
             config: valence_splitter_library::msg::LibraryConfig {
-                input_addr: LibraryAccountType::Addr(input_addr.to_string()),
+                input_addr: LibraryAccountType::Addr(plan.input_account.address.to_string()),
                 splits,
             },
         };
 
-    let contract_address = instantiate_wasm_contract(
-        config.spliter_code_id,
-        &serde_json::to_string(&split_lib_instantiate_msg)?,
-        config,
-        "splitter",
-    )?;
-    Ok(contract_address)
+    batch.instantiate2(&plan.splitter_library, &split_lib_instantiate_msg)
 }
 
-pub fn instantiate_and_approve_astroport_libraries(
+pub fn instantiate_astroport_libraries(
     config: &Config,
-    pools: &Vec<PoolInfo>,
-    split_output_accounts: &Vec<String>,
-    liquidity_output_accounts: &Vec<String>,
-    processor_address: &String,
+    batch: &mut TxBatch,
+    pools: &[PoolInfo],
+    plan: &ProgramPlan,
 ) -> Result<(Vec<String>, Vec<String>)> {
     let mut astroport_lper_lib_addresses = Vec::new();
     let mut astroport_withdraw_lib_addresses = Vec::new();
+    let split_output_accounts = addresses(&plan.split_output_accounts);
+    let liquidity_output_accounts = addresses(&plan.liquidity_output_accounts);
+
+    for (i, pool) in pools.iter().enumerate() {
+        let split_output_account = &split_output_accounts[i];
+        let liquidity_output_account = &liquidity_output_accounts[i];
 
-    for ((pool, split_output_account), liquidity_output_account) in pools
-        .iter()
-        .zip(split_output_accounts)
-        .zip(liquidity_output_accounts)
-    {
         let astroport_lper_lib_address = instantiate_astro_lper_library(
             config,
+            batch,
             pool,
+            &plan.astroport_lper_libraries[i],
             split_output_account,
             liquidity_output_account,
-            processor_address,
-        )?;
-        approve_library(config, split_output_account, &astroport_lper_lib_address)?;
-        approve_library(
-            config,
-            liquidity_output_account,
-            &astroport_lper_lib_address,
+            &plan.processor.address,
         )?;
         astroport_lper_lib_addresses.push(astroport_lper_lib_address);
 
         let astroport_withdraw_lib_address = instantiate_astro_withdraw_library(
             config,
+            batch,
             pool,
+            &plan.astroport_withdraw_libraries[i],
             liquidity_output_account,
-            processor_address,
-        )?;
-        approve_library(
-            config,
-            liquidity_output_account,
-            &astroport_withdraw_lib_address,
+            &plan.processor.address,
         )?;
         astroport_withdraw_lib_addresses.push(astroport_withdraw_lib_address);
     }
@@ -170,7 +276,9 @@ pub fn instantiate_and_approve_astroport_libraries(
 
 fn instantiate_astro_lper_library(
     config: &Config,
+    batch: &mut TxBatch,
     pool: &PoolInfo,
+    library: &PlannedContract,
     input_addr: &String,
     output_addr: &String,
     processor_addr: &String,
@@ -194,18 +302,14 @@ fn instantiate_astro_lper_library(
             },
         };
 
-    let contract_address = instantiate_wasm_contract(
-        config.astro_lper_code_id,
-        &serde_json::to_string(&astro_lper_instantiate_msg)?,
-        config,
-        "astro_lper",
-    )?;
-    Ok(contract_address)
+    batch.instantiate2(library, &astro_lper_instantiate_msg)
 }
 
 fn instantiate_astro_withdraw_library(
     config: &Config,
+    batch: &mut TxBatch,
     pool: &PoolInfo,
+    library: &PlannedContract,
     input_addr: &String,
     processor_addr: &String,
 ) -> Result<String> {
@@ -228,35 +332,22 @@ fn instantiate_astro_withdraw_library(
         },
     };
 
-    let contract_address = instantiate_wasm_contract(
-        config.astro_withdraw_code_id,
-        &serde_json::to_string(&astro_withdraw_instantiate_msg)?,
-        config,
-        "astro_lper",
-    )?;
-    Ok(contract_address)
+    batch.instantiate2(library, &astro_withdraw_instantiate_msg)
 }
 
-pub fn instantiate_authorization_and_processor(config: &Config) -> Result<(String, String)> {
-    // predict authorization address
-    let authorization_salt = generate_salt();
-    let code_hash = get_code_hash(config, config.authorization_code_id)?;
-    let predicted_auth_address = predict_contract_address(
-        &config.tool_operator_address,
-        &authorization_salt,
-        &code_hash,
-    )?;
-
-    // init processor
+pub fn instantiate_authorization_and_processor(
+    config: &Config,
+    batch: &mut TxBatch,
+    plan: &ProgramPlan,
+) -> Result<(String, String)> {
+    // init processor with the predicted authorization address
     let mut processor_instantiate_msg = HashMap::new();
-    processor_instantiate_msg.insert("authorization_contract", predicted_auth_address.to_string());
+    processor_instantiate_msg.insert(
+        "authorization_contract",
+        plan.authorization.address.to_string(),
+    );
 
-    let processor_address = instantiate_wasm_contract(
-        config.processor_code_id,
-        &serde_json::to_string(&processor_instantiate_msg)?,
-        config,
-        "processor",
-    )?;
+    let processor_address = batch.instantiate2(&plan.processor, &processor_instantiate_msg)?;
 
     // init authorization
     let mut authorization_instantiate_msg = HashMap::new();
@@ -267,13 +358,8 @@ pub fn instantiate_authorization_and_processor(config: &Config) -> Result<(Strin
     authorization_instantiate_msg.insert("processor", Value::String(processor_address.to_string()));
     authorization_instantiate_msg.insert("sub_owners", serde_json::to_value(Vec::<String>::new())?);
 
-    let authorization_address = instantiate2_wasm_contract(
-        config.authorization_code_id,
-        &serde_json::to_string(&authorization_instantiate_msg)?,
-        config,
-        "authorization",
-        &authorization_salt,
-    )?;
+    let authorization_address =
+        batch.instantiate2(&plan.authorization, &authorization_instantiate_msg)?;
 
     println!("Authorization Address: {}", authorization_address);
     println!("Processor Address: {}", processor_address);
@@ -281,43 +367,21 @@ pub fn instantiate_authorization_and_processor(config: &Config) -> Result<(Strin
     Ok((authorization_address, processor_address))
 }
 
-/// Generates a hex-encoded timestamp to use as the salt
-fn generate_salt() -> String {
-    let timestamp = Utc::now().timestamp(); // Get the current Unix timestamp
-    format!("{:x}", timestamp) // Convert to hex string
-}
-
-pub fn approve_library(config: &Config, account: &String, library_address: &String) -> Result<()> {
-    let create_authorization_msg = &ExecuteMsg::ApproveLibrary {
-        library: library_address.to_string(),
-    };
-    execute_wasm_contract(
-        account,
-        &serde_json::to_string(&create_authorization_msg)?,
-        config,
-    )?;
-
-    Ok(())
-}
-
 pub fn transfer_accounts_ownership(
     config: &Config,
+    batch: &mut TxBatch,
     account_addresses: &[String],
-    new_owner_addr: &String,
+    new_owner_addr: &str,
 ) -> Result<()> {
     for account_address in account_addresses {
         let update_acc_ownership_msg = valence_account_utils::msg::ExecuteMsg::UpdateOwnership(
             cw_ownable::Action::TransferOwnership {
                 new_owner: new_owner_addr.to_string(),
-                expiry: None,
+                expiry: ownership_transfer_expiry(config),
             },
         );
 
-        execute_wasm_contract(
-            account_address,
-            &serde_json::to_string(&update_acc_ownership_msg)?,
-            config,
-        )?;
+        batch.execute(account_address, &update_acc_ownership_msg)?;
     }
 
     Ok(())
@@ -326,122 +390,500 @@ pub fn transfer_accounts_ownership(
 pub fn get_filtered_authorizations(
     auth_contract_address: &str,
     action: ProgramAction,
+    pool: Option<&str>,
     config: &Config,
 ) -> Result<Vec<Authorization>, Error> {
-    let label_suffix = match action {
+    let label_suffix = action_label_suffix(&action);
+    let filtered_authorizations = find_authorizations(auth_contract_address, action, pool, config)?;
+
+    if let Some(pool) = pool {
+        if filtered_authorizations.is_empty() {
+            return Err(Error::msg(format!(
+                "No per-pool {} authorization found for pool {}, pools are selected by the name they were deployed with, or by address if they have none",
+                label_suffix, pool
+            )));
+        }
+    }
+
+    Ok(filtered_authorizations)
+}
+
+fn action_label_suffix(action: &ProgramAction) -> &'static str {
+    match action {
         ProgramAction::Deploy => DEPLOY,
         ProgramAction::Withdraw => WITHDRAW,
-    };
+    }
+}
+
+/// Same as `get_filtered_authorizations`, but a pool without per-pool authorizations
+/// gets an empty list instead of an error
+pub fn find_authorizations(
+    auth_contract_address: &str,
+    action: ProgramAction,
+    pool: Option<&str>,
+    config: &Config,
+) -> Result<Vec<Authorization>, Error> {
+    let label_suffix = action_label_suffix(&action);
 
     let authorizations: Vec<Authorization> = get_authorizations(config, auth_contract_address)?;
 
     // Filter by label
-    let filtered_authorizations = authorizations
+    let action_authorizations: Vec<Authorization> = authorizations
         .into_iter()
         .filter(|auth| auth.label.ends_with(label_suffix))
         .collect();
 
+    let stems: Vec<String> = action_authorizations
+        .iter()
+        .map(|auth| label_stem(&auth.label, label_suffix))
+        .collect();
+
+    // Disabled authorizations still count for the stems, since they keep their labels
+    let label_prefixes = program_label_prefixes(&stems);
+    let mut filtered_authorizations = Vec::new();
+    for authorization in action_authorizations
+        .into_iter()
+        .filter(Authorization::is_enabled)
+    {
+        let stem = label_stem(&authorization.label, label_suffix);
+        let per_pool = is_per_pool_stem(&stem, &stems);
+
+        let selected = match pool {
+            None => !per_pool,
+            Some(pool) => per_pool && is_pool_stem(&stem, &label_prefixes, pool),
+        };
+
+        if selected {
+            filtered_authorizations.push(authorization);
+        }
+    }
+
     Ok(filtered_authorizations)
 }
 
-pub fn build_deploy_subroutine(
-    split_lib_address: &String,
-    astroport_lper_lib_addresses: &Vec<String>,
-) -> Subroutine {
-    let mut deploy_subroutine_builder = AtomicSubroutineBuilder::new();
+/// Strips the `_<action>` suffix from an authorization label
+fn label_stem(label: &str, label_suffix: &str) -> String {
+    label
+        .strip_suffix(label_suffix)
+        .unwrap_or(label)
+        .trim_end_matches(DELIMITER)
+        .to_string()
+}
 
-    deploy_subroutine_builder = deploy_subroutine_builder.with_function(
+/// Per-pool labels are `<prefix>_<pool>_<action>`, so their stem extends the stem
+/// of the program-wide `<prefix>_<action>` authorization.
+fn is_per_pool_stem(stem: &str, stems: &[String]) -> bool {
+    stems
+        .iter()
+        .any(|other| other != stem && stem.starts_with(&format!("{}{}", other, DELIMITER)))
+}
+
+/// Label prefixes of the program-wide authorizations, without the `.<revision>` of the
+/// authorizations recreated after the pools of the program changed
+fn program_label_prefixes(stems: &[String]) -> Vec<&str> {
+    stems
+        .iter()
+        .filter(|stem| !is_per_pool_stem(stem, stems))
+        .map(|stem| strip_revision(stem))
+        .collect()
+}
+
+/// Whether the stem is exactly `<prefix>_<pool>` for one of the program label prefixes,
+/// in any revision
+fn is_pool_stem(stem: &str, label_prefixes: &[&str], pool: &str) -> bool {
+    label_prefixes
+        .iter()
+        .any(|prefix| strip_revision(stem) == format!("{}{}{}", prefix, DELIMITER, pool))
+}
+
+/// Splits the `.<revision>` the tool appends to the stems of recreated authorizations
+fn split_revision(stem: &str) -> (&str, Option<u32>) {
+    match stem.rsplit_once('.') {
+        Some((base, revision))
+            if !revision.is_empty() && revision.chars().all(|c| c.is_ascii_digit()) =>
+        {
+            (base, revision.parse().ok())
+        }
+        _ => (stem, None),
+    }
+}
+
+fn strip_revision(stem: &str) -> &str {
+    split_revision(stem).0
+}
+
+/// Splits a label into its stem and `_<action>` suffix, if it has one
+fn split_action(label: &str) -> (&str, Option<&str>) {
+    for action in [DEPLOY, WITHDRAW] {
+        if let Some(stem) = label
+            .strip_suffix(action)
+            .and_then(|stem| stem.strip_suffix(DELIMITER))
+        {
+            return (stem, Some(action));
+        }
+    }
+
+    (label, None)
+}
+
+/// Label of an authorization recreated in place of the given one: `<stem>.<revision>[_<action>]`
+/// with the revision following the highest one among the labels
+pub fn revised_label(label: &str, labels: &[String]) -> String {
+    let (stem, action) = split_action(label);
+    let base = strip_revision(stem);
+    let revision = labels
+        .iter()
+        .filter_map(|other| {
+            let (other_stem, other_action) = split_action(other);
+            match split_revision(other_stem) {
+                (other_base, revision) if other_base == base && other_action == action => {
+                    Some(revision.unwrap_or(0))
+                }
+                _ => None,
+            }
+        })
+        .max()
+        .unwrap_or(0)
+        + 1;
+
+    match action {
+        Some(action) => format!("{}.{}{}{}", base, revision, DELIMITER, action),
+        None => format!("{}.{}", base, revision),
+    }
+}
+
+/// Builds the authorization replacing an existing one with a different mode. The mode of an
+/// authorization can't be modified, so the subroutine and settings of the existing one are
+/// copied under a revised label. Returns it with the expiration of the existing authorization,
+/// which is set with a modification since authorizations are created with a duration.
+pub fn build_mode_change_authorization(
+    config: &Config,
+    auth_contract_address: &str,
+    label: &str,
+    mode: &AuthorizationModeSpec,
+) -> Result<(AuthorizationInfo, Expiration)> {
+    let authorizations = query_wasm_smart(
+        config,
+        auth_contract_address,
+        r#"{"authorizations":{"start_after":null,"limit":100}}"#,
+    )?;
+    let authorizations = authorizations
+        .as_array()
+        .ok_or_else(|| Error::msg("Invalid authorizations response"))?;
+    let existing = authorizations
+        .iter()
+        .find(|authorization| authorization["label"].as_str() == Some(label))
+        .ok_or_else(|| Error::msg(format!("Authorization {} not found", label)))?;
+
+    let labels: Vec<String> = authorizations
+        .iter()
+        .filter_map(|authorization| authorization["label"].as_str().map(String::from))
+        .collect();
+    let subroutine: Subroutine = serde_json::from_value(existing["subroutine"].clone())
+        .with_context(|| format!("Failed to parse the subroutine of authorization {}", label))?;
+
+    let mut authorization_builder = AuthorizationBuilder::new()
+        .with_label(&revised_label(label, &labels))
+        .with_mode(mode.to_mode_info(config))
+        .with_subroutine(subroutine)
+        .with_not_before(serde_json::from_value(existing["not_before"].clone())?);
+    if let Some(max_concurrent_executions) = existing["max_concurrent_executions"].as_u64() {
+        authorization_builder =
+            authorization_builder.with_max_concurrent_executions(max_concurrent_executions);
+    }
+    if !existing["priority"].is_null() {
+        authorization_builder = authorization_builder
+            .with_priority(serde_json::from_value(existing["priority"].clone())?);
+    }
+
+    Ok((
+        authorization_builder.build(),
+        serde_json::from_value(existing["expiration"].clone())?,
+    ))
+}
+
+/// Adds a `process_function` call of the given library function to the subroutine
+fn with_process_function(
+    subroutine_builder: AtomicSubroutineBuilder,
+    library_address: &str,
+    function_name: &str,
+) -> AtomicSubroutineBuilder {
+    subroutine_builder.with_function(
         AtomicFunctionBuilder::new()
-            .with_contract_address(LibraryAccountType::Addr(split_lib_address.to_string()))
+            .with_contract_address(LibraryAccountType::Addr(library_address.to_string()))
             .with_message_details(MessageDetails {
                 message_type: MessageType::CosmwasmExecuteMsg,
                 message: Message {
                     name: "process_function".to_string(),
                     params_restrictions: Some(vec![ParamRestriction::MustBeIncluded(vec![
                         "process_function".to_string(),
-                        "split".to_string(),
+                        function_name.to_string(),
                     ])]),
                 },
             })
             .build(),
-    );
+    )
+}
+
+pub fn build_deploy_subroutine(
+    split_lib_address: &str,
+    astroport_lper_lib_addresses: &[String],
+) -> Subroutine {
+    let mut deploy_subroutine_builder =
+        with_process_function(AtomicSubroutineBuilder::new(), split_lib_address, SPLIT);
 
     for astroport_lper_lib_address in astroport_lper_lib_addresses {
-        deploy_subroutine_builder = deploy_subroutine_builder.with_function(
-            AtomicFunctionBuilder::new()
-                .with_contract_address(LibraryAccountType::Addr(
-                    astroport_lper_lib_address.to_string(),
-                ))
-                .with_message_details(MessageDetails {
-                    message_type: MessageType::CosmwasmExecuteMsg,
-                    message: Message {
-                        name: "process_function".to_string(),
-                        params_restrictions: Some(vec![ParamRestriction::MustBeIncluded(vec![
-                            "process_function".to_string(),
-                            "provide_double_sided_liquidity".to_string(),
-                        ])]),
-                    },
-                })
-                .build(),
+        deploy_subroutine_builder = with_process_function(
+            deploy_subroutine_builder,
+            astroport_lper_lib_address,
+            PROVIDE_DOUBLE_SIDED_LIQUIDITY,
         );
     }
 
     deploy_subroutine_builder.build()
 }
 
-pub fn build_withdraw_subroutine(astroport_withdraw_lib_addresses: &Vec<String>) -> Subroutine {
+pub fn build_withdraw_subroutine(astroport_withdraw_lib_addresses: &[String]) -> Subroutine {
     let mut withdraw_subroutine_builder = AtomicSubroutineBuilder::new();
 
     for astroport_withdraw_lib_address in astroport_withdraw_lib_addresses {
-        withdraw_subroutine_builder = withdraw_subroutine_builder.with_function(
-            AtomicFunctionBuilder::new()
-                .with_contract_address(LibraryAccountType::Addr(
-                    astroport_withdraw_lib_address.to_string(),
-                ))
-                .with_message_details(MessageDetails {
-                    message_type: MessageType::CosmwasmExecuteMsg,
-                    message: Message {
-                        name: "process_function".to_string(),
-                        params_restrictions: Some(vec![ParamRestriction::MustBeIncluded(vec![
-                            "process_function".to_string(),
-                            "withdraw_liquidity".to_string(),
-                        ])]),
-                    },
-                })
-                .build(),
+        withdraw_subroutine_builder = with_process_function(
+            withdraw_subroutine_builder,
+            astroport_withdraw_lib_address,
+            WITHDRAW_LIQUIDITY,
         );
     }
 
     withdraw_subroutine_builder.build()
 }
 
+/// Builds the deploy and withdraw subroutines of every pool.
+/// The per-pool deploy only provides liquidity from the pool's split output account,
+/// since the splitter moves the funds of all pools at once.
+pub fn build_pool_subroutines(
+    pools: &[PoolInfo],
+    astroport_lper_lib_addresses: &[String],
+    astroport_withdraw_lib_addresses: &[String],
+) -> Vec<(String, Subroutine, Subroutine)> {
+    pools
+        .iter()
+        .zip(astroport_lper_lib_addresses)
+        .zip(astroport_withdraw_lib_addresses)
+        .map(|((pool, lper_address), withdraw_address)| {
+            let deploy_subroutine = with_process_function(
+                AtomicSubroutineBuilder::new(),
+                lper_address,
+                PROVIDE_DOUBLE_SIDED_LIQUIDITY,
+            )
+            .build();
+            let withdraw_subroutine = with_process_function(
+                AtomicSubroutineBuilder::new(),
+                withdraw_address,
+                WITHDRAW_LIQUIDITY,
+            )
+            .build();
+
+            (
+                pool.label_id().to_string(),
+                deploy_subroutine,
+                withdraw_subroutine,
+            )
+        })
+        .collect()
+}
+
+fn build_authorization(
+    label: &str,
+    subroutine: Subroutine,
+    spec: &AuthorizationSpec,
+    config: &Config,
+) -> AuthorizationInfo {
+    let mut authorization_builder = AuthorizationBuilder::new()
+        .with_label(label)
+        .with_mode(spec.mode.to_mode_info(config))
+        .with_subroutine(subroutine);
+
+    if let Some(not_before) = &spec.not_before {
+        authorization_builder = authorization_builder.with_not_before(*not_before);
+    }
+    if let Some(duration) = &spec.duration {
+        authorization_builder = authorization_builder.with_duration(duration.clone());
+    }
+    if let Some(max_concurrent_executions) = spec.max_concurrent_executions {
+        authorization_builder =
+            authorization_builder.with_max_concurrent_executions(max_concurrent_executions);
+    }
+    if let Some(priority) = &spec.priority {
+        authorization_builder = authorization_builder.with_priority(priority.clone());
+    }
+
+    authorization_builder.build()
+}
+
+/// Builds the program-wide `<stem>_<action>` and per-pool `<prefix>_<pool>_<action>` authorizations
+fn build_program_authorizations(
+    program_stem: &str,
+    label_prefix: &str,
+    deploy_subroutine: Subroutine,
+    withdraw_subroutine: Subroutine,
+    pool_subroutines: Vec<(String, Subroutine, Subroutine)>,
+    authorizations_spec: &AuthorizationsSpec,
+    config: &Config,
+) -> Vec<AuthorizationInfo> {
+    let mut authorizations = vec![
+        build_authorization(
+            &format!("{}{}{}", program_stem, DELIMITER, DEPLOY),
+            deploy_subroutine,
+            &authorizations_spec.deploy,
+            config,
+        ),
+        build_authorization(
+            &format!("{}{}{}", program_stem, DELIMITER, WITHDRAW),
+            withdraw_subroutine,
+            &authorizations_spec.withdraw,
+            config,
+        ),
+    ];
+
+    for (pool_id, pool_deploy_subroutine, pool_withdraw_subroutine) in pool_subroutines {
+        authorizations.push(build_authorization(
+            &format!(
+                "{}{}{}{}{}",
+                label_prefix, DELIMITER, pool_id, DELIMITER, DEPLOY
+            ),
+            pool_deploy_subroutine,
+            &authorizations_spec.pool_deploy,
+            config,
+        ));
+        authorizations.push(build_authorization(
+            &format!(
+                "{}{}{}{}{}",
+                label_prefix, DELIMITER, pool_id, DELIMITER, WITHDRAW
+            ),
+            pool_withdraw_subroutine,
+            &authorizations_spec.pool_withdraw,
+            config,
+        ));
+    }
+
+    authorizations
+}
+
+/// Messages replacing the program-wide authorizations after the pools of a program changed.
+/// The subroutine of an authorization can't be changed, so the enabled program-wide deploy and
+/// withdraw authorizations are disabled and recreated as `<prefix>.<revision>_<action>`
+/// with the new libraries. Per-pool authorizations of the new pools are created and those of
+/// the removed pool are disabled.
+#[allow(clippy::too_many_arguments)]
+pub fn build_authorizations_update_msgs(
+    config: &Config,
+    auth_contract_address: &str,
+    label_prefix: &str,
+    splitter_library: &str,
+    astroport_lper_lib_addresses: &[String],
+    astroport_withdraw_lib_addresses: &[String],
+    pool_subroutines: Vec<(String, Subroutine, Subroutine)>,
+    removed_pool_id: Option<&str>,
+    authorizations_spec: &AuthorizationsSpec,
+) -> Result<Vec<ContractMsg>> {
+    let authorizations = get_authorizations(config, auth_contract_address)?;
+
+    let mut retired_labels = Vec::new();
+    for action in [ProgramAction::Deploy, ProgramAction::Withdraw] {
+        retired_labels.extend(
+            get_filtered_authorizations(auth_contract_address, action, None, config)?
+                .into_iter()
+                .map(|authorization| authorization.label),
+        );
+    }
+    if let Some(pool_id) = removed_pool_id {
+        // per-pool authorizations of the pool in every revision
+        let pool_stem = format!("{}{}{}", label_prefix, DELIMITER, pool_id);
+        retired_labels.extend(
+            authorizations
+                .iter()
+                .filter(|authorization| {
+                    let (stem, action) = split_action(&authorization.label);
+                    action.is_some()
+                        && strip_revision(stem) == pool_stem
+                        && authorization.is_enabled()
+                })
+                .map(|authorization| authorization.label.clone()),
+        );
+    }
+
+    let revision = authorizations
+        .iter()
+        .filter_map(|authorization| {
+            authorization
+                .label
+                .strip_prefix(&format!("{}.", label_prefix))?
+                .split(DELIMITER)
+                .next()?
+                .parse::<u32>()
+                .ok()
+        })
+        .max()
+        .unwrap_or(0)
+        + 1;
+    let program_stem = format!("{}.{}", label_prefix, revision);
+
+    let mut msgs = retired_labels
+        .into_iter()
+        .map(|label| {
+            ContractMsg::new(
+                auth_contract_address,
+                &valence_authorization_utils::msg::ExecuteMsg::PermissionedAction(
+                    valence_authorization_utils::msg::PermissionedMsg::DisableAuthorization {
+                        label,
+                    },
+                ),
+            )
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let new_authorizations = build_program_authorizations(
+        &program_stem,
+        label_prefix,
+        build_deploy_subroutine(splitter_library, astroport_lper_lib_addresses),
+        build_withdraw_subroutine(astroport_withdraw_lib_addresses),
+        pool_subroutines,
+        authorizations_spec,
+        config,
+    );
+    msgs.push(ContractMsg::new(
+        auth_contract_address,
+        &valence_authorization_utils::msg::ExecuteMsg::PermissionedAction(
+            valence_authorization_utils::msg::PermissionedMsg::CreateAuthorizations {
+                authorizations: new_authorizations,
+            },
+        ),
+    )?);
+
+    Ok(msgs)
+}
+
 pub fn create_and_execute_authorization(
-    authorization_address: &String,
+    authorization_address: &str,
     deploy_subroutine: Subroutine,
     withdraw_subroutine: Subroutine,
+    pool_subroutines: Vec<(String, Subroutine, Subroutine)>,
+    authorizations_spec: &AuthorizationsSpec,
     config: &Config,
-    label_prefix: &String,
+    label_prefix: &str,
 ) -> Result<()> {
-    let deploy_authorization = AuthorizationBuilder::new()
-        .with_label(&format!("{}{}{}", label_prefix, DELIMITER, DEPLOY))
-        .with_mode(AuthorizationModeInfo::Permissioned(
-            PermissionTypeInfo::WithoutCallLimit(vec![config.tool_operator_address.to_string()]),
-        ))
-        .with_subroutine(deploy_subroutine)
-        .build();
-
-    let withdraw_authorization = AuthorizationBuilder::new()
-        .with_label(&format!("{}{}{}", label_prefix, DELIMITER, WITHDRAW))
-        .with_mode(AuthorizationModeInfo::Permissioned(
-            PermissionTypeInfo::WithoutCallLimit(vec![config.tool_operator_address.to_string()]),
-        ))
-        .with_subroutine(withdraw_subroutine)
-        .build();
+    let authorizations = build_program_authorizations(
+        label_prefix,
+        label_prefix,
+        deploy_subroutine,
+        withdraw_subroutine,
+        pool_subroutines,
+        authorizations_spec,
+        config,
+    );
 
     let create_authorization_msg = valence_authorization_utils::msg::ExecuteMsg::PermissionedAction(
-        valence_authorization_utils::msg::PermissionedMsg::CreateAuthorizations {
-            authorizations: vec![deploy_authorization, withdraw_authorization],
-        },
+        valence_authorization_utils::msg::PermissionedMsg::CreateAuthorizations { authorizations },
     );
 
     execute_wasm_contract(
@@ -455,50 +897,77 @@ pub fn create_and_execute_authorization(
 
 pub fn transfer_ownership(
     config: &Config,
-    authorization_address: &String,
-    input_account: &String,
-    split_output_accounts: &Vec<String>,
-    liquidity_output_accounts: &Vec<String>,
-    withdrawal_accounts: &Vec<String>,
+    authorization_address: &str,
+    input_account: &str,
+    split_output_accounts: &[String],
+    liquidity_output_accounts: &[String],
+    withdrawal_accounts: &[String],
 ) -> Result<()> {
     // Transfer ownership of the authorization contract
     let update_auth_ownership_msg = valence_authorization_utils::msg::ExecuteMsg::UpdateOwnership(
         cw_ownable::Action::TransferOwnership {
             new_owner: config.neutron_dao_committee_address.to_string(),
-            expiry: None,
+            expiry: ownership_transfer_expiry(config),
         },
     );
 
-    execute_wasm_contract(
-        authorization_address,
-        &serde_json::to_string(&update_auth_ownership_msg)?,
-        config,
-    )?;
+    let mut batch = TxBatch::new(config);
+    batch.execute(authorization_address, &update_auth_ownership_msg)?;
 
     // Collect all accounts
-    let mut all_accounts = vec![input_account.clone()];
-    all_accounts.extend_from_slice(&split_output_accounts);
-    all_accounts.extend_from_slice(&liquidity_output_accounts);
-    all_accounts.extend_from_slice(&withdrawal_accounts);
+    let mut all_accounts = vec![input_account.to_string()];
+    all_accounts.extend_from_slice(split_output_accounts);
+    all_accounts.extend_from_slice(liquidity_output_accounts);
+    all_accounts.extend_from_slice(withdrawal_accounts);
 
     // Transfer ownership of all accounts
-    transfer_accounts_ownership(config, &all_accounts, &config.neutron_dao_committee_address)?;
+    transfer_accounts_ownership(
+        config,
+        &mut batch,
+        &all_accounts,
+        &config.neutron_dao_committee_address,
+    )?;
+    batch.broadcast()?;
 
     println!("Ownership change needs to be accepted for the following accounts:");
     for acc in &all_accounts {
         println!("{}", acc);
     }
+    println!(
+        "Run verify-ownership --auth-contract-address {} to track the acceptance",
+        authorization_address
+    );
 
     Ok(())
 }
 
+/// Expiry of pending ownership transfers, if configured
+fn ownership_transfer_expiry(config: &Config) -> Option<Expiration> {
+    config.ownership_transfer_expiry_seconds.map(|seconds| {
+        Expiration::AtTime(Timestamp::from_seconds(
+            Utc::now().timestamp() as u64 + seconds,
+        ))
+    })
+}
+
+/// Builds the accept ownership message, which is the same for the authorization contract and Valence accounts
+pub fn build_accept_ownership_msg(contract_address: &str) -> Result<ContractMsg> {
+    ContractMsg::new(
+        contract_address,
+        &valence_account_utils::msg::ExecuteMsg::UpdateOwnership(
+            cw_ownable::Action::AcceptOwnership,
+        ),
+    )
+}
+
 /// Computes the predicted contract address based on CosmWasm's derivation formula.
 pub fn predict_contract_address(
     creator: &str,
     salt: &str,
     code_hash: &str,
+    bech32_prefix: &str,
 ) -> Result<String, Error> {
-    let creator_canonical = addr_canonicalize(creator)?;
+    let creator_canonical = addr_canonicalize(creator, bech32_prefix)?;
     let code_hash_bytes = HexBinary::from_hex(code_hash).unwrap();
     let salt_bytes = HexBinary::from_hex(salt).unwrap();
 
@@ -507,54 +976,90 @@ pub fn predict_contract_address(
         instantiate2_address(&code_hash_bytes, &creator_canonical, &salt_bytes)?;
 
     // Convert canonical address back to human-readable address
-    let addr = addr_humanize(&predicted_address)?;
+    let addr = addr_humanize(&predicted_address, bech32_prefix)?;
 
     // Return the address as a string
     Ok(addr.into_string())
 }
-/// Converts a Bech32 address to canonical format.
-fn addr_canonicalize(input: &str) -> Result<CanonicalAddr, Error> {
-    let hrp_str =
-        CheckedHrpstring::new::<Bech32>(input).map_err(|_| Error::msg("Error decoding bech32"))?;
 
-    // Ensure the Bech32 prefix is correct
-    if !hrp_str
-        .hrp()
-        .as_bytes()
-        .eq_ignore_ascii_case(NEUTRON_BECH32_PREFIX.as_bytes())
-    {
-        return Err(Error::msg("Wrong bech32 prefix").into());
+/// Checks the configured bech32 prefix against the chain and, when signing with the keyring,
+/// the operator address against the operator key
+pub fn validate_network(config: &Config) -> Result<(), Error> {
+    let chain_prefix = get_bech32_prefix(config)?;
+    if chain_prefix != config.bech32_prefix {
+        return Err(Error::msg(format!(
+            "Configured bech32 prefix {} does not match the chain's prefix {}",
+            config.bech32_prefix, chain_prefix
+        )));
     }
 
-    // Collect bytes from the decoded Bech32 address
-    let bytes: Vec<u8> = hrp_str.byte_iter().collect();
-
-    // Validate the address length
-    validate_length(&bytes)?;
+    if config.signer == SignerKind::Keyring {
+        let key_address = get_key_address(config)?;
+        if key_address != config.tool_operator_address.as_str() {
+            return Err(Error::msg(format!(
+                "Operator address {} does not match the address {} of the key {}",
+                config.tool_operator_address, key_address, config.tool_operator_moniker
+            )));
+        }
+    }
 
-    // Return the address as a CanonicalAddr
-    Ok(bytes.into())
+    Ok(())
 }
 
-/// Converts a canonical address back to a human-readable address.
-fn addr_humanize(canonical: &CanonicalAddr) -> Result<Addr, Error> {
-    // Validate the canonical address length
-    validate_length(canonical.as_ref())?;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    // Parse the Bech32 prefix
-    let prefix =
-        Hrp::parse(NEUTRON_BECH32_PREFIX).map_err(|_| Error::msg("Invalid bech32 prefix"))?;
+    fn stems(labels: &[&str]) -> Vec<String> {
+        labels
+            .iter()
+            .map(|label| label_stem(label, DEPLOY))
+            .collect()
+    }
 
-    // Encode the canonical address back to Bech32 format
-    encode::<Bech32>(prefix, canonical.as_slice())
-        .map(Addr::unchecked)
-        .map_err(|_| Error::msg("Bech32 encoding error"))
-}
+    #[test]
+    fn label_stem_strips_action_suffix() {
+        assert_eq!(label_stem("prog_deploy", DEPLOY), "prog");
+        assert_eq!(label_stem("prog_usdc_deploy", DEPLOY), "prog_usdc");
+        assert_eq!(label_stem("prog.2_deploy", DEPLOY), "prog.2");
+    }
+
+    #[test]
+    fn per_pool_stems_extend_program_stem() {
+        let stems = stems(&["prog_deploy", "prog_usdc_deploy", "prog.1_deploy"]);
+        assert!(!is_per_pool_stem("prog", &stems));
+        assert!(!is_per_pool_stem("prog.1", &stems));
+        assert!(is_per_pool_stem("prog_usdc", &stems));
+    }
+
+    #[test]
+    fn program_label_prefixes_drop_revision() {
+        let stems = stems(&["prog_deploy", "prog.2_deploy", "prog_usdc_deploy"]);
+        assert_eq!(program_label_prefixes(&stems), vec!["prog", "prog"]);
+    }
+
+    #[test]
+    fn revised_label_follows_highest_revision() {
+        let labels: Vec<String> = ["prog_deploy", "prog.2_deploy", "prog.3_withdraw", "custom"]
+            .iter()
+            .map(|label| label.to_string())
+            .collect();
+        assert_eq!(revised_label("prog_deploy", &labels), "prog.3_deploy");
+        assert_eq!(revised_label("prog.2_deploy", &labels), "prog.3_deploy");
+        assert_eq!(revised_label("prog_withdraw", &labels), "prog.4_withdraw");
+        assert_eq!(
+            revised_label("prog_usdc_deploy", &labels),
+            "prog_usdc.1_deploy"
+        );
+        assert_eq!(revised_label("custom", &labels), "custom.1");
+    }
 
-/// Basic validation for the number of bytes in a canonical address
-fn validate_length(bytes: &[u8]) -> Result<()> {
-    match bytes.len() {
-        1..=255 => Ok(()),
-        _ => Err(Error::msg("Invalid canonical address length")),
+    #[test]
+    fn pool_stem_matches_exact_pool() {
+        let prefixes = ["prog"];
+        assert!(is_pool_stem("prog_a", &prefixes, "a"));
+        assert!(!is_pool_stem("prog_usdc_a", &prefixes, "a"));
+        assert!(!is_pool_stem("prog_ab", &prefixes, "b"));
+        assert!(is_pool_stem("prog_a.2", &prefixes, "a"));
     }
 }
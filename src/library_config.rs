@@ -0,0 +1,403 @@
+use crate::address::Address;
+use crate::cli::{LibraryConfigCommand, SplitInfo};
+use crate::dao::ContractMsg;
+use anyhow::{anyhow, Context, Result};
+use serde_json::{json, Value};
+use valence_astroport_lper::msg::LiquidityProviderConfig;
+use valence_library_utils::{denoms::UncheckedDenom, LibraryAccountType};
+
+/// A changed field of a library config, with both values in the shape the library reports its config
+pub struct ConfigChange {
+    pub field: &'static str,
+    pub current: Value,
+    pub new: Value,
+}
+
+/// Compares the requested changes with the on-chain config of the library and builds the
+/// `update_config` message with only the fields that differ
+pub fn build_config_update(
+    library: &LibraryConfigCommand,
+    current: &Value,
+) -> Result<(Vec<ConfigChange>, ContractMsg)> {
+    let mut changes = Vec::new();
+
+    let msg = match library {
+        LibraryConfigCommand::Splitter {
+            library_address,
+            input_address,
+            split,
+        } => {
+            let input_addr = diff_address(
+                &mut changes,
+                current,
+                "input_addr",
+                input_address.as_deref(),
+            );
+            let splits = if split.is_empty() {
+                None
+            } else {
+                diff(&mut changes, current, "splits", reported_splits(split))
+                    .then(|| split.iter().map(unchecked_split).collect())
+            };
+
+            ContractMsg::new(
+                library_address,
+                &valence_library_utils::msg::ExecuteMsg::<
+                    valence_splitter_library::msg::FunctionMsgs,
+                    _,
+                >::UpdateConfig {
+                    new_config: valence_splitter_library::msg::LibraryConfigUpdate {
+                        input_addr,
+                        splits,
+                    },
+                },
+            )?
+        }
+        LibraryConfigCommand::Lper {
+            library_address,
+            input_address,
+            output_address,
+            pool_address,
+            max_spread,
+        } => {
+            let input_addr = diff_address(
+                &mut changes,
+                current,
+                "input_addr",
+                input_address.as_deref(),
+            );
+            let output_addr = diff_address(
+                &mut changes,
+                current,
+                "output_addr",
+                output_address.as_deref(),
+            );
+            let pool_addr =
+                diff_string(&mut changes, current, "pool_addr", pool_address.as_deref());
+
+            // max spread can only be changed with the rest of the LP config, which is kept as is
+            let mut lp_config = None;
+            if let Some(max_spread) = max_spread {
+                let mut new_lp_config: LiquidityProviderConfig =
+                    serde_json::from_value(current["lp_config"].clone())
+                        .context("Failed to parse the LP config of the library")?;
+                new_lp_config.max_spread = Some(*max_spread);
+                if diff(
+                    &mut changes,
+                    current,
+                    "lp_config",
+                    serde_json::to_value(&new_lp_config)?,
+                ) {
+                    lp_config = Some(new_lp_config);
+                }
+            }
+
+            ContractMsg::new(
+                library_address,
+                &valence_library_utils::msg::ExecuteMsg::<
+                    valence_astroport_lper::msg::FunctionMsgs,
+                    _,
+                >::UpdateConfig {
+                    new_config: valence_astroport_lper::msg::LibraryConfigUpdate {
+                        input_addr,
+                        output_addr,
+                        pool_addr,
+                        lp_config,
+                    },
+                },
+            )?
+        }
+        LibraryConfigCommand::Withdrawer {
+            library_address,
+            input_address,
+            output_address,
+            pool_address,
+        } => {
+            let input_addr = diff_address(
+                &mut changes,
+                current,
+                "input_addr",
+                input_address.as_deref(),
+            );
+            let output_addr = diff_address(
+                &mut changes,
+                current,
+                "output_addr",
+                output_address.as_deref(),
+            );
+            let pool_addr =
+                diff_string(&mut changes, current, "pool_addr", pool_address.as_deref());
+
+            ContractMsg::new(
+                library_address,
+                &valence_library_utils::msg::ExecuteMsg::<
+                    valence_astroport_withdrawer::msg::FunctionMsgs,
+                    _,
+                >::UpdateConfig {
+                    new_config: valence_astroport_withdrawer::msg::LibraryConfigUpdate {
+                        input_addr,
+                        output_addr,
+                        pool_addr,
+                        withdrawer_config: None,
+                    },
+                },
+            )?
+        }
+    };
+
+    Ok((changes, msg))
+}
+
+/// Checks that the new pool of an LPer library trades the assets of its LP config with the
+/// same pool type, as the LP config is kept when only the pool changes.
+/// `pair` is the answer of the pool to the Astroport `pair` query.
+pub fn check_lper_pool(current: &Value, pair: &Value, bech32_prefix: &str) -> Result<()> {
+    let lp_config = &current["lp_config"];
+    let mut library_assets = [
+        lp_config["asset_data"]["asset1"]
+            .as_str()
+            .unwrap_or_default(),
+        lp_config["asset_data"]["asset2"]
+            .as_str()
+            .unwrap_or_default(),
+    ];
+    let mut pool_assets: Vec<&str> = pair["asset_infos"]
+        .as_array()
+        .map(|assets| {
+            assets
+                .iter()
+                .filter_map(|asset| {
+                    asset["native_token"]["denom"]
+                        .as_str()
+                        .or_else(|| asset["token"]["contract_addr"].as_str())
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    library_assets.sort_unstable();
+    pool_assets.sort_unstable();
+    if pool_assets != library_assets {
+        return Err(anyhow!(
+            "The new pool trades {}, the LPer library provides {}, deploy a new library for the pool",
+            pool_assets.join(" / "),
+            library_assets.join(" / ")
+        ));
+    }
+
+    // Pools with cw20 LP tokens report the token contract, native LP tokens are denoms
+    let liquidity_token = pair["liquidity_token"].as_str().unwrap_or_default();
+    let lp_token_kind = if Address::parse(liquidity_token, bech32_prefix).is_ok() {
+        "cw20_lp_token"
+    } else {
+        "native_lp_token"
+    };
+    let pool_type = json!({ lp_token_kind: pair["pair_type"] });
+    if lp_config["pool_type"] != pool_type {
+        return Err(anyhow!(
+            "The new pool is a {} pool, the LPer library is configured for a {} pool, deploy a new library for the pool",
+            pool_type,
+            lp_config["pool_type"]
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn print_config_diff(library_address: &str, changes: &[ConfigChange]) {
+    println!("== Config changes of {} ==", library_address);
+    for change in changes {
+        println!("{}", change.field);
+        println!("  - {}", change.current);
+        println!("  + {}", change.new);
+    }
+}
+
+/// Records the change if the new value differs from the on-chain one
+fn diff(changes: &mut Vec<ConfigChange>, current: &Value, field: &'static str, new: Value) -> bool {
+    if current[field] == new {
+        return false;
+    }
+
+    changes.push(ConfigChange {
+        field,
+        current: current[field].clone(),
+        new,
+    });
+    true
+}
+
+fn diff_string(
+    changes: &mut Vec<ConfigChange>,
+    current: &Value,
+    field: &'static str,
+    new: Option<&str>,
+) -> Option<String> {
+    let new = new?;
+    diff(changes, current, field, json!(new)).then(|| new.to_string())
+}
+
+fn diff_address(
+    changes: &mut Vec<ConfigChange>,
+    current: &Value,
+    field: &'static str,
+    new: Option<&str>,
+) -> Option<LibraryAccountType> {
+    diff_string(changes, current, field, new).map(LibraryAccountType::Addr)
+}
+
+fn unchecked_split(split: &SplitInfo) -> valence_splitter_library::msg::UncheckedSplitConfig {
+    valence_splitter_library::msg::UncheckedSplitConfig {
+        denom: UncheckedDenom::Native(split.denom.clone()),
+        account: LibraryAccountType::Addr(split.account.to_string()),
+        amount: valence_splitter_library::msg::UncheckedSplitAmount::FixedAmount(
+            split.amount.into(),
+        ),
+    }
+}
+
+/// Splits as the splitter reports them in its config, with checked denoms and plain addresses
+fn reported_splits(splits: &[SplitInfo]) -> Value {
+    splits
+        .iter()
+        .map(|split| {
+            json!({
+                "denom": { "native": split.denom },
+                "account": split.account.as_str(),
+                "amount": { "fixed_amount": split.amount.to_string() },
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIBRARY: &str = "neutron1qqqsyqcyq5rqwzqfpg9scrgwpugpzysn8fcct7";
+    const ACCOUNT: &str = "neutron1qurswpc8qurswpc8qurswpc8qurswpc8hvwc6x";
+
+    fn address(address: &str) -> Address {
+        Address::parse(address, "neutron").unwrap()
+    }
+
+    fn lper_config() -> Value {
+        json!({
+            "input_addr": LIBRARY,
+            "output_addr": ACCOUNT,
+            "pool_addr": LIBRARY,
+            "lp_config": {
+                "pool_type": { "native_lp_token": { "xyk": {} } },
+                "asset_data": { "asset1": "untrn", "asset2": "uusdc" },
+                "max_spread": null,
+            },
+        })
+    }
+
+    fn pair(assets: [&str; 2], liquidity_token: &str, pair_type: Value) -> Value {
+        json!({
+            "asset_infos": [
+                { "native_token": { "denom": assets[0] } },
+                { "native_token": { "denom": assets[1] } },
+            ],
+            "liquidity_token": liquidity_token,
+            "pair_type": pair_type,
+        })
+    }
+
+    #[test]
+    fn splitter_update_contains_only_changed_fields() {
+        let current = json!({
+            "input_addr": LIBRARY,
+            "splits": [{
+                "denom": { "native": "untrn" },
+                "account": ACCOUNT,
+                "amount": { "fixed_amount": "10" },
+            }],
+        });
+        let split = SplitInfo {
+            account: address(ACCOUNT),
+            denom: "untrn".to_string(),
+            amount: 10,
+        };
+
+        let (changes, msg) = build_config_update(
+            &LibraryConfigCommand::Splitter {
+                library_address: address(LIBRARY),
+                input_address: Some(address(ACCOUNT)),
+                split: vec![split.clone()],
+            },
+            &current,
+        )
+        .unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].field, "input_addr");
+        assert_eq!(msg.contract_address, LIBRARY);
+        let new_config = &msg.msg["update_config"]["new_config"];
+        assert_eq!(
+            new_config["input_addr"],
+            serde_json::to_value(LibraryAccountType::Addr(ACCOUNT.to_string())).unwrap()
+        );
+        assert!(new_config["splits"].is_null());
+
+        let (changes, _) = build_config_update(
+            &LibraryConfigCommand::Splitter {
+                library_address: address(LIBRARY),
+                input_address: None,
+                split: vec![SplitInfo {
+                    amount: 20,
+                    ..split
+                }],
+            },
+            &current,
+        )
+        .unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].field, "splits");
+    }
+
+    #[test]
+    fn lper_update_without_changes_is_empty() {
+        let (changes, _) = build_config_update(
+            &LibraryConfigCommand::Lper {
+                library_address: address(LIBRARY),
+                input_address: Some(address(LIBRARY)),
+                output_address: Some(address(ACCOUNT)),
+                pool_address: Some(address(LIBRARY)),
+                max_spread: None,
+            },
+            &lper_config(),
+        )
+        .unwrap();
+        assert!(changes.is_empty());
+    }
+
+    #[test]
+    fn lper_pool_must_match_the_lp_config() {
+        let current = lper_config();
+        let native_lp = "factory/neutron1pool/astroport/share";
+        assert!(check_lper_pool(
+            &current,
+            &pair(["uusdc", "untrn"], native_lp, json!({ "xyk": {} })),
+            "neutron"
+        )
+        .is_ok());
+        assert!(check_lper_pool(
+            &current,
+            &pair(["uatom", "untrn"], native_lp, json!({ "xyk": {} })),
+            "neutron"
+        )
+        .is_err());
+        assert!(check_lper_pool(
+            &current,
+            &pair(["untrn", "uusdc"], native_lp, json!({ "stable": {} })),
+            "neutron"
+        )
+        .is_err());
+        assert!(check_lper_pool(
+            &current,
+            &pair(["untrn", "uusdc"], ACCOUNT, json!({ "xyk": {} })),
+            "neutron"
+        )
+        .is_err());
+    }
+}
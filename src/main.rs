@@ -1,38 +1,344 @@
+mod address;
 mod authorization;
+mod batch;
 mod cli;
 mod config;
+mod daemon;
+mod dao;
+mod fees;
+mod funds;
+mod graph;
 mod handlers;
 mod helpers;
+mod library_config;
+mod manifest;
+mod metrics;
 mod node_cmd;
+mod plan;
+mod processor;
+mod program;
+mod program_spec;
+mod proposal;
+mod scheduler;
+mod signer;
+mod status;
 mod wasm;
 
 use crate::cli::Cli;
 use crate::config::load_config;
-use crate::handlers::{create_program, execute_program, tick_processor};
+use crate::daemon::TickDaemonOptions;
+use crate::handlers::{
+    add_authorizations, add_pool, create_program, execute_program, fund_program, inspect_program,
+    modify_authorization, processor_queue, program_diagram, program_proposal, program_status,
+    remove_pool, schedule_add, schedule_list, schedule_remove, set_authorization_enabled, sweep,
+    tick_daemon, tick_processor, update_library_config, verify_ownership,
+};
+use crate::helpers::validate_network;
+use crate::program_spec::load_program_spec;
+use crate::scheduler::{run_scheduler, ScheduleTrigger};
 use anyhow::Result;
 use clap::Parser;
+use std::time::Duration;
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
-    let config = load_config()?;
+    let mut config = load_config()?;
+    config.offline_dir = cli.offline_dir.clone();
+    if cli.command.uses_operator() {
+        validate_network(&config)?;
+    }
 
     match &cli.command {
         cli::Commands::CreateProgram {
             label_prefix,
             pools,
+            per_pool_authorizations,
+            program_spec,
+            salt,
+            dry_run,
+            estimate,
         } => {
-            create_program(label_prefix, pools, &config)?;
+            let program_spec = load_program_spec(program_spec.as_deref(), &config.bech32_prefix)?;
+            create_program(
+                label_prefix,
+                pools,
+                *per_pool_authorizations,
+                &program_spec,
+                salt.as_deref(),
+                *dry_run,
+                *estimate,
+                &config,
+            )?;
         }
         cli::Commands::ExecuteProgram {
             auth_contract_address,
             action,
+            pool,
+            wait,
+            max_iterations,
         } => {
-            execute_program(auth_contract_address, action.clone(), &config)?;
+            execute_program(
+                auth_contract_address,
+                action.clone(),
+                pool.as_deref(),
+                *wait,
+                *max_iterations,
+                &config,
+            )?;
         }
         cli::Commands::TickProcessor {
             processor_contract_address,
+            until_empty,
+            max_iterations,
+        } => {
+            tick_processor(
+                processor_contract_address,
+                *until_empty,
+                *max_iterations,
+                &config,
+            )?;
+        }
+        cli::Commands::TickDaemon {
+            processor_contract_addresses,
+            poll_interval_secs,
+            failure_cooldown_secs,
+            retry_cooldown_secs,
+            gas_budget,
+            metrics_address,
+        } => {
+            tick_daemon(
+                &TickDaemonOptions {
+                    processors: processor_contract_addresses
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect(),
+                    poll_interval: Duration::from_secs(*poll_interval_secs),
+                    failure_cooldown: Duration::from_secs(*failure_cooldown_secs),
+                    retry_cooldown: Duration::from_secs(*retry_cooldown_secs),
+                    gas_budget: *gas_budget,
+                    metrics_address: metrics_address.clone(),
+                },
+                &config,
+            )?;
+        }
+        cli::Commands::ScheduleAdd {
+            schedule_file,
+            auth_contract_address,
+            action,
+            pool,
+            at,
+            cron,
+            wait,
+            sync_authorization_window,
+            window_secs,
+            proposal,
         } => {
-            tick_processor(processor_contract_address, &config)?;
+            let trigger = match (at, cron) {
+                (Some(timestamp), _) => ScheduleTrigger::At {
+                    timestamp: *timestamp,
+                },
+                (None, Some(expression)) => ScheduleTrigger::Cron {
+                    expression: expression.clone(),
+                },
+                (None, None) => unreachable!("clap requires --at or --cron"),
+            };
+            schedule_add(
+                schedule_file,
+                auth_contract_address,
+                action.clone(),
+                pool.as_deref(),
+                trigger,
+                *wait,
+                sync_authorization_window.then_some((*window_secs, *proposal)),
+                &config,
+            )?;
+        }
+        cli::Commands::ScheduleList { schedule_file } => {
+            schedule_list(schedule_file)?;
+        }
+        cli::Commands::ScheduleRemove { schedule_file, id } => {
+            schedule_remove(schedule_file, *id)?;
+        }
+        cli::Commands::ScheduleRun {
+            schedule_file,
+            poll_interval_secs,
+            max_iterations,
+            max_retries,
+        } => {
+            run_scheduler(
+                schedule_file,
+                Duration::from_secs(*poll_interval_secs),
+                *max_iterations,
+                *max_retries,
+                &config,
+            )?;
+        }
+        cli::Commands::ProcessorQueue {
+            processor_contract_address,
+        } => {
+            processor_queue(processor_contract_address, &config)?;
+        }
+        cli::Commands::AddAuthorizations {
+            auth_contract_address,
+            authorizations_file,
+            proposal,
+        } => {
+            add_authorizations(
+                auth_contract_address,
+                authorizations_file,
+                *proposal,
+                &config,
+            )?;
+        }
+        cli::Commands::DisableAuthorization {
+            auth_contract_address,
+            label,
+            proposal,
+        } => {
+            set_authorization_enabled(auth_contract_address, label, false, *proposal, &config)?;
+        }
+        cli::Commands::EnableAuthorization {
+            auth_contract_address,
+            label,
+            proposal,
+        } => {
+            set_authorization_enabled(auth_contract_address, label, true, *proposal, &config)?;
+        }
+        cli::Commands::ModifyAuthorization {
+            auth_contract_address,
+            label,
+            not_before,
+            expiration,
+            max_concurrent_executions,
+            priority,
+            mint,
+            mode,
+            mode_address,
+            call_limit,
+            proposal,
+        } => {
+            modify_authorization(
+                auth_contract_address,
+                label,
+                *not_before,
+                *expiration,
+                *max_concurrent_executions,
+                priority.clone().map(Into::into),
+                mint.clone(),
+                mode.as_ref()
+                    .map(|mode| mode.to_spec(mode_address, *call_limit)),
+                *proposal,
+                &config,
+            )?;
+        }
+        cli::Commands::Status {
+            auth_contract_address,
+            manifest,
+        } => {
+            program_status(auth_contract_address, manifest.as_deref(), &config)?;
+        }
+        cli::Commands::VerifyOwnership {
+            auth_contract_address,
+            manifest,
+            reissue_expired,
+        } => {
+            verify_ownership(
+                auth_contract_address,
+                manifest.as_deref(),
+                *reissue_expired,
+                &config,
+            )?;
+        }
+        cli::Commands::FundProgram {
+            manifest,
+            proposal,
+            treasury_address,
+        } => {
+            fund_program(manifest, *proposal, treasury_address.as_deref(), &config)?;
+        }
+        cli::Commands::Sweep {
+            auth_contract_address,
+            manifest,
+            recipient,
+            execute,
+        } => {
+            sweep(
+                auth_contract_address,
+                manifest.as_deref(),
+                recipient,
+                !*execute,
+                &config,
+            )?;
+        }
+        cli::Commands::AddPool {
+            manifest,
+            pool,
+            per_pool_authorizations,
+            program_spec,
+            execute,
+        } => {
+            let program_spec = load_program_spec(program_spec.as_deref(), &config.bech32_prefix)?;
+            add_pool(
+                manifest,
+                pool,
+                *per_pool_authorizations,
+                &program_spec,
+                !*execute,
+                &config,
+            )?;
+        }
+        cli::Commands::RemovePool {
+            manifest,
+            pool,
+            skip_withdraw,
+            max_iterations,
+            program_spec,
+            execute,
+        } => {
+            let program_spec = load_program_spec(program_spec.as_deref(), &config.bech32_prefix)?;
+            remove_pool(
+                manifest,
+                pool,
+                *skip_withdraw,
+                *max_iterations,
+                &program_spec,
+                !*execute,
+                &config,
+            )?;
+        }
+        cli::Commands::Inspect {
+            auth_contract_address,
+            manifest,
+            format,
+            output,
+        } => {
+            inspect_program(
+                auth_contract_address,
+                manifest.as_deref(),
+                format,
+                output.as_deref(),
+                &config,
+            )?;
+        }
+        cli::Commands::Diagram {
+            auth_contract_address,
+            manifest,
+            format,
+            output,
+        } => {
+            program_diagram(
+                auth_contract_address.as_deref(),
+                manifest.as_deref(),
+                format,
+                output.as_deref(),
+                &config,
+            )?;
+        }
+        cli::Commands::Proposal { manifest, output } => {
+            program_proposal(manifest, output.as_deref(), &config)?;
+        }
+        cli::Commands::UpdateConfig { library, execute } => {
+            update_library_config(library, !*execute, &config)?;
         }
     }
     Ok(())
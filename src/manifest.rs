@@ -0,0 +1,181 @@
+use crate::address::Address;
+use crate::cli::PoolInfo;
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+/// Addresses of everything deployed by `create-program`, saved next to the tool
+/// so the program can be inspected or recovered later
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProgramManifest {
+    pub label_prefix: String,
+    pub authorization_address: Address,
+    pub processor_address: Address,
+    pub input_account: Address,
+    pub split_output_accounts: Vec<Address>,
+    pub liquidity_output_accounts: Vec<Address>,
+    pub withdrawal_accounts: Vec<Address>,
+    pub splitter_library: Address,
+    pub astroport_lper_libraries: Vec<Address>,
+    pub astroport_withdraw_libraries: Vec<Address>,
+    pub pools: Vec<PoolInfo>,
+    // salt nonce of the instantiate2 addresses, missing in manifests of older deployments
+    #[serde(default)]
+    pub salt: Option<String>,
+}
+
+impl ProgramManifest {
+    /// Every address recorded in the manifest, contracts and pools
+    pub fn addresses(&self) -> Vec<&Address> {
+        let mut addresses = vec![
+            &self.authorization_address,
+            &self.processor_address,
+            &self.input_account,
+            &self.splitter_library,
+        ];
+        for accounts in [
+            &self.split_output_accounts,
+            &self.liquidity_output_accounts,
+            &self.withdrawal_accounts,
+            &self.astroport_lper_libraries,
+            &self.astroport_withdraw_libraries,
+        ] {
+            addresses.extend(accounts);
+        }
+        addresses.extend(self.pools.iter().map(|pool| &pool.address));
+        addresses
+    }
+}
+
+/// Manifest file of a deployment. The salt nonce keeps repeated deployments with the same
+/// label prefix, which get new addresses, from sharing a file.
+pub fn manifest_path(label_prefix: &str, salt: Option<&str>) -> String {
+    match salt {
+        Some(salt) => format!("{}_{}_program.json", label_prefix, salt),
+        None => format!("{}_program.json", label_prefix),
+    }
+}
+
+/// Fails if the manifest file already exists, so a deployment can check it before broadcasting
+pub fn check_manifest_absent(path: &str) -> Result<()> {
+    if Path::new(path).exists() {
+        return Err(anyhow!(
+            "Program manifest {} already exists, it records another deployment",
+            path
+        ));
+    }
+
+    Ok(())
+}
+
+/// Writes the manifest of a new deployment, refusing to overwrite an existing file
+pub fn create_manifest(manifest: &ProgramManifest) -> Result<String> {
+    let path = manifest_path(&manifest.label_prefix, manifest.salt.as_deref());
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .with_context(|| format!("Failed to create program manifest {}", path))?;
+    file.write_all(serde_json::to_string_pretty(manifest)?.as_bytes())
+        .with_context(|| format!("Failed to write program manifest {}", path))?;
+    Ok(path)
+}
+
+/// Updated manifest of a program change that is not applied on chain yet,
+/// e.g. `ntrn_program.json` gets `ntrn_program.pending.json`
+pub fn pending_manifest_path(path: &str) -> String {
+    match path.strip_suffix(".json") {
+        Some(stem) => format!("{}.pending.json", stem),
+        None => format!("{}.pending", path),
+    }
+}
+
+/// Writes the updated manifest beside the loaded one, which stays untouched until
+/// the change is applied on chain
+pub fn save_pending_manifest(path: &str, manifest: &ProgramManifest) -> Result<String> {
+    let pending_path = pending_manifest_path(path);
+    fs::write(&pending_path, serde_json::to_string_pretty(manifest)?)
+        .with_context(|| format!("Failed to write program manifest {}", pending_path))?;
+    Ok(pending_path)
+}
+
+/// Replaces the loaded manifest with the pending one once the change is applied on chain
+pub fn apply_pending_manifest(path: &str) -> Result<()> {
+    let pending_path = pending_manifest_path(path);
+    fs::rename(&pending_path, path).with_context(|| {
+        format!(
+            "Failed to replace program manifest {} with {}",
+            path, pending_path
+        )
+    })
+}
+
+/// Loads a manifest and checks all its addresses against the network prefix,
+/// since manifests may have been edited by hand
+pub fn load_manifest(path: &str, bech32_prefix: &str) -> Result<ProgramManifest> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read program manifest {}", path))?;
+    let manifest: ProgramManifest = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse program manifest {}", path))?;
+    for address in manifest.addresses() {
+        address
+            .validate(bech32_prefix)
+            .with_context(|| format!("Invalid address in program manifest {}", path))?;
+    }
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pending_manifest_sits_beside_the_manifest() {
+        assert_eq!(
+            pending_manifest_path("ntrn_0123456789abcdef_program.json"),
+            "ntrn_0123456789abcdef_program.pending.json"
+        );
+        assert_eq!(
+            pending_manifest_path("manifests/ntrn_program.json"),
+            "manifests/ntrn_program.pending.json"
+        );
+        assert_eq!(pending_manifest_path("manifest"), "manifest.pending");
+    }
+
+    #[test]
+    fn load_manifest_checks_contract_addresses_against_the_prefix() {
+        let neutron = "neutron1qqqsyqcyq5rqwzqfpg9scrgwpugpzysn8fcct7";
+        let cosmos = "cosmos1qqqsyqcyq5rqwzqfpg9scrgwpugpzysnrk363e";
+        let manifest = |processor_address: &str| {
+            serde_json::json!({
+                "label_prefix": "ntrn",
+                "authorization_address": neutron,
+                "processor_address": processor_address,
+                "input_account": neutron,
+                "split_output_accounts": [neutron],
+                "liquidity_output_accounts": [neutron],
+                "withdrawal_accounts": [neutron],
+                "splitter_library": neutron,
+                "astroport_lper_libraries": [neutron],
+                "astroport_withdraw_libraries": [neutron],
+                "pools": [],
+            })
+        };
+        let path =
+            std::env::temp_dir().join(format!("ld_tool_manifest_{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        fs::write(path, manifest(neutron).to_string()).unwrap();
+        let loaded = load_manifest(path, "neutron").unwrap();
+        assert_eq!(loaded.addresses().len(), 9);
+
+        fs::write(path, manifest(cosmos).to_string()).unwrap();
+        assert!(load_manifest(path, "neutron").is_err());
+
+        fs::write(path, manifest("not an address").to_string()).unwrap();
+        assert!(load_manifest(path, "neutron").is_err());
+        fs::remove_file(path).unwrap();
+    }
+}
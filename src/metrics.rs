@@ -0,0 +1,98 @@
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Minimal Prometheus registry: metric name and labels mapped to a value
+#[derive(Default)]
+pub struct Metrics {
+    counters: Mutex<BTreeMap<(String, String), f64>>,
+    gauges: Mutex<BTreeMap<(String, String), f64>>,
+}
+
+impl Metrics {
+    pub fn inc_counter(&self, name: &str, labels: &str, value: f64) {
+        let mut counters = self.counters.lock().unwrap();
+        *counters
+            .entry((name.to_string(), labels.to_string()))
+            .or_insert(0.0) += value;
+    }
+
+    pub fn set_gauge(&self, name: &str, labels: &str, value: f64) {
+        self.gauges
+            .lock()
+            .unwrap()
+            .insert((name.to_string(), labels.to_string()), value);
+    }
+
+    /// Renders the metrics in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut output = String::new();
+        for (kind, metrics) in [("counter", &self.counters), ("gauge", &self.gauges)] {
+            let metrics = metrics.lock().unwrap();
+            let mut last_name = "";
+            for ((name, labels), value) in metrics.iter() {
+                if name != last_name {
+                    output.push_str(&format!("# TYPE {} {}\n", name, kind));
+                    last_name = name;
+                }
+                output.push_str(&format!("{}{{{}}} {}\n", name, labels, value));
+            }
+        }
+        output
+    }
+}
+
+/// Serves the metrics over HTTP on the given address from a background thread
+pub fn serve_metrics(address: &str, metrics: Arc<Metrics>) -> std::io::Result<()> {
+    let listener = TcpListener::bind(address)?;
+    println!("Serving metrics on http://{}/metrics", address);
+
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            // Every path answers with the metrics, the request itself is not needed
+            let mut buffer = [0; 1024];
+            let _ = stream.read(&mut buffer);
+
+            let body = metrics.render();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_groups_metrics_by_name() {
+        let metrics = Metrics::default();
+        metrics.inc_counter("ticks_total", "processor=\"a\"", 1.0);
+        metrics.inc_counter("ticks_total", "processor=\"a\"", 2.0);
+        metrics.inc_counter("ticks_total", "processor=\"b\"", 1.0);
+        metrics.set_gauge("queue_depth", "processor=\"a\"", 4.0);
+        metrics.set_gauge("queue_depth", "processor=\"a\"", 2.0);
+
+        assert_eq!(
+            metrics.render(),
+            "# TYPE ticks_total counter\n\
+            ticks_total{processor=\"a\"} 3\n\
+            ticks_total{processor=\"b\"} 1\n\
+            # TYPE queue_depth gauge\n\
+            queue_depth{processor=\"a\"} 2\n"
+        );
+    }
+
+    #[test]
+    fn render_empty_registry() {
+        assert_eq!(Metrics::default().render(), "");
+    }
+}
@@ -1,19 +1,44 @@
-use crate::config::Config;
-use anyhow::{anyhow, Error};
-use std::process::Command;
+use crate::config::{Config, SignerKind};
+use anyhow::{anyhow, Context, Error};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
 use std::thread::sleep;
 use std::time::Duration;
 
 pub fn build_tx_flags(config: &Config) -> String {
-    format!(
+    // Without the key in the keyring, transactions are only generated and signed by the signer
+    let from = match config.signer {
+        SignerKind::Keyring => config.tool_operator_moniker.as_str(),
+        SignerKind::Mnemonic | SignerKind::Offline => config.tool_operator_address.as_str(),
+    };
+
+    let mut flags = format!(
         "--from={} --gas auto --gas-adjustment {} --gas-prices {} --chain-id={} \
-        --keyring-backend=test --output=json --home {} --node {} -y",
-        config.tool_operator_moniker,
+        --keyring-backend={} --output=json --home {} --node {} -y",
+        from,
         config.gas_adjustment,
         config.gas_price,
         config.neutron_chain_id,
+        config.keyring_backend,
         config.home,
         config.neutron_rpc
+    );
+    if let Some(fee_granter) = &config.fee_granter {
+        flags.push_str(&format!(" --fee-granter={}", fee_granter));
+    }
+    if let Some(fee_payer) = &config.fee_payer {
+        flags.push_str(&format!(" --fee-payer={}", fee_payer));
+    }
+
+    flags
+}
+
+/// Flags for signing a transaction file generated with `--generate-only`
+pub fn build_sign_flags(config: &Config, from: &str, keyring_backend: &str, home: &str) -> String {
+    format!(
+        "--from={} --chain-id={} --keyring-backend={} --home {} --node {} --output=json",
+        from, config.neutron_chain_id, keyring_backend, home, config.neutron_rpc
     )
 }
 
@@ -32,14 +57,72 @@ pub fn build_wasm_instantiate_flags(config: &Config, label: &str) -> String {
 }
 
 pub fn run_command(cmd: &str) -> Result<String, Error> {
-    let output = Command::new("sh")
+    run_command_with_input(cmd, None)
+}
+
+/// Runs a command that opens the keyring, answering the passphrase prompts of the `file` backend
+pub fn run_tx_command(cmd: &str, config: &Config) -> Result<String, Error> {
+    // The file keyring may ask for the passphrase more than once per command
+    let input = keyring_passphrase(config)?.map(|passphrase| format!("{}\n", passphrase).repeat(3));
+    run_command_with_input(cmd, input.as_deref())
+}
+
+/// Passphrase of the `file` keyring holding the operator key, asked on the terminal once
+/// per run when it is not configured
+fn keyring_passphrase(config: &Config) -> Result<Option<String>, Error> {
+    static PROMPTED_PASSPHRASE: OnceLock<String> = OnceLock::new();
+
+    if let Some(passphrase) = &config.keyring_passphrase {
+        return Ok(Some(passphrase.clone()));
+    }
+    // Other signers only generate transactions, which doesn't open the keyring
+    if config.keyring_backend != "file" || config.signer != SignerKind::Keyring {
+        return Ok(None);
+    }
+    if let Some(passphrase) = PROMPTED_PASSPHRASE.get() {
+        return Ok(Some(passphrase.clone()));
+    }
+
+    let passphrase = rpassword::prompt_password("Keyring passphrase: ")
+        .context("Failed to read the keyring passphrase")?;
+    Ok(Some(PROMPTED_PASSPHRASE.get_or_init(|| passphrase).clone()))
+}
+
+/// Same as `run_command`, writing the input to the command's stdin
+pub fn run_command_with_input(cmd: &str, input: Option<&str>) -> Result<String, Error> {
+    run_command_internal(cmd, input).inspect_err(|_| {
+        println!("Running command failed: {}", cmd);
+    })
+}
+
+/// Same as `run_command`, without reporting the failed command (used when polling)
+pub fn run_command_silent(cmd: &str) -> Result<String, Error> {
+    run_command_internal(cmd, None)
+}
+
+fn run_command_internal(cmd: &str, input: Option<&str>) -> Result<String, Error> {
+    let mut child = Command::new("sh")
         .arg("-c")
         .arg(cmd)
-        .output()
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow!("Failed to execute command: {}", e))?;
+
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        stdin.write_all(input.as_bytes())?;
+    }
+
+    let output = child
+        .wait_with_output()
         .map_err(|e| anyhow!("Failed to execute command: {}", e))?;
 
     if !output.status.success() {
-        println!("Running command failed: {}", cmd);
         return Err(anyhow!(
             "Command failed with status: {}\nstderr: {}",
             output.status,
@@ -47,8 +130,20 @@ pub fn run_command(cmd: &str) -> Result<String, Error> {
         ));
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-    // Wait a few seconds for transaction to be indexed
-    sleep(Duration::from_secs(5));
-    Ok(stdout)
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Polls the command until it succeeds, e.g. until a broadcasted transaction is indexed
+pub fn poll_command(cmd: &str, attempts: u32, interval: Duration) -> Result<String, Error> {
+    let mut last_error = anyhow!("Command was not run: {}", cmd);
+    for _ in 0..attempts {
+        sleep(interval);
+        match run_command_silent(cmd) {
+            Ok(output) => return Ok(output),
+            Err(e) => last_error = e,
+        }
+    }
+
+    println!("Running command failed: {}", cmd);
+    Err(last_error)
 }
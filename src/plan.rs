@@ -0,0 +1,299 @@
+use crate::config::Config;
+use crate::helpers::predict_contract_address;
+use crate::wasm::{contract_exists, get_code_hash};
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+/// A contract to be created with instantiate2, with its address known before it exists
+#[derive(Debug, Serialize, Clone)]
+pub struct PlannedContract {
+    pub label: String,
+    pub code_id: u64,
+    pub salt: String,
+    pub address: String,
+}
+
+/// Addresses of every contract of a program, computed before anything is deployed
+#[derive(Debug, Serialize, Clone)]
+pub struct ProgramPlan {
+    pub salt_nonce: String,
+    pub authorization: PlannedContract,
+    pub processor: PlannedContract,
+    pub input_account: PlannedContract,
+    pub split_output_accounts: Vec<PlannedContract>,
+    pub liquidity_output_accounts: Vec<PlannedContract>,
+    pub withdrawal_accounts: Vec<PlannedContract>,
+    pub splitter_library: PlannedContract,
+    pub astroport_lper_libraries: Vec<PlannedContract>,
+    pub astroport_withdraw_libraries: Vec<PlannedContract>,
+}
+
+impl ProgramPlan {
+    /// All planned contracts, in deployment order
+    pub fn contracts(&self) -> Vec<&PlannedContract> {
+        let mut contracts = vec![&self.processor, &self.authorization, &self.input_account];
+        contracts.extend(&self.split_output_accounts);
+        contracts.extend(&self.liquidity_output_accounts);
+        contracts.extend(&self.withdrawal_accounts);
+        contracts.push(&self.splitter_library);
+        contracts.extend(&self.astroport_lper_libraries);
+        contracts.extend(&self.astroport_withdraw_libraries);
+        contracts
+    }
+}
+
+/// Contracts of one pool added to an existing program
+#[derive(Debug, Serialize, Clone)]
+pub struct PoolPlan {
+    pub index: usize,
+    pub split_output_account: PlannedContract,
+    pub liquidity_output_account: PlannedContract,
+    pub withdrawal_account: PlannedContract,
+    pub astroport_lper_library: PlannedContract,
+    pub astroport_withdraw_library: PlannedContract,
+}
+
+impl PoolPlan {
+    pub fn contracts(&self) -> Vec<&PlannedContract> {
+        vec![
+            &self.split_output_account,
+            &self.liquidity_output_account,
+            &self.withdrawal_account,
+            &self.astroport_lper_library,
+            &self.astroport_withdraw_library,
+        ]
+    }
+}
+
+pub fn addresses(contracts: &[PlannedContract]) -> Vec<String> {
+    contracts
+        .iter()
+        .map(|contract| contract.address.clone())
+        .collect()
+}
+
+/// Computes the address of every contract of the program from the operator address,
+/// the code checksums and one salt per role
+pub fn plan_program(
+    label_prefix: &str,
+    pools_count: usize,
+    salt_nonce: &str,
+    config: &Config,
+) -> Result<ProgramPlan> {
+    let mut planner = Planner {
+        label_prefix,
+        salt_nonce,
+        config,
+        code_hashes: HashMap::new(),
+    };
+
+    Ok(ProgramPlan {
+        salt_nonce: salt_nonce.to_string(),
+        authorization: planner.plan("authorization", config.authorization_code_id)?,
+        processor: planner.plan("processor", config.processor_code_id)?,
+        input_account: planner.plan("input", config.base_account_code_id)?,
+        split_output_accounts: planner.plan_per_pool(
+            "split_out",
+            config.base_account_code_id,
+            pools_count,
+        )?,
+        liquidity_output_accounts: planner.plan_per_pool(
+            "liquidity_out",
+            config.base_account_code_id,
+            pools_count,
+        )?,
+        withdrawal_accounts: planner.plan_per_pool(
+            "withdrawal",
+            config.base_account_code_id,
+            pools_count,
+        )?,
+        splitter_library: planner.plan("splitter", config.spliter_code_id)?,
+        astroport_lper_libraries: planner.plan_per_pool(
+            "lper",
+            config.astro_lper_code_id,
+            pools_count,
+        )?,
+        astroport_withdraw_libraries: planner.plan_per_pool(
+            "withdrawer",
+            config.astro_withdraw_code_id,
+            pools_count,
+        )?,
+    })
+}
+
+/// Plans the contracts of a pool added to a deployed program. Pools removed earlier keep
+/// their contracts, so the first pool index without any contract on chain is used.
+pub fn plan_pool(
+    label_prefix: &str,
+    first_index: usize,
+    salt_nonce: &str,
+    config: &Config,
+) -> Result<PoolPlan> {
+    let mut planner = Planner {
+        label_prefix,
+        salt_nonce,
+        config,
+        code_hashes: HashMap::new(),
+    };
+
+    let mut index = first_index;
+    loop {
+        let plan = PoolPlan {
+            index,
+            split_output_account: planner.plan_for_pool(
+                "split_out",
+                config.base_account_code_id,
+                index,
+            )?,
+            liquidity_output_account: planner.plan_for_pool(
+                "liquidity_out",
+                config.base_account_code_id,
+                index,
+            )?,
+            withdrawal_account: planner.plan_for_pool(
+                "withdrawal",
+                config.base_account_code_id,
+                index,
+            )?,
+            astroport_lper_library: planner.plan_for_pool(
+                "lper",
+                config.astro_lper_code_id,
+                index,
+            )?,
+            astroport_withdraw_library: planner.plan_for_pool(
+                "withdrawer",
+                config.astro_withdraw_code_id,
+                index,
+            )?,
+        };
+        if find_existing(&plan.contracts(), config)?.is_empty() {
+            return Ok(plan);
+        }
+        index += 1;
+    }
+}
+
+/// Fails if a contract already exists at any planned address, as instantiate2 would fail on it
+pub fn check_plan_collisions(plan: &ProgramPlan, config: &Config) -> Result<()> {
+    let existing = find_existing(&plan.contracts(), config)?;
+
+    if !existing.is_empty() {
+        return Err(anyhow!(
+            "Contracts already exist at the planned addresses, use a different --salt: {}",
+            existing.join(", ")
+        ));
+    }
+
+    Ok(())
+}
+
+fn find_existing(contracts: &[&PlannedContract], config: &Config) -> Result<Vec<String>> {
+    let mut existing = Vec::new();
+    for contract in contracts {
+        if contract_exists(config, &contract.address)? {
+            existing.push(format!("{} ({})", contract.label, contract.address));
+        }
+    }
+
+    Ok(existing)
+}
+
+/// Random nonce mixed into the salts, so repeated deployments with the same label prefix get new addresses
+pub fn generate_salt_nonce() -> String {
+    format!("{:016x}", rand::random::<u64>())
+}
+
+pub fn print_plan(plan: &ProgramPlan) {
+    println!("== Planned contracts ==");
+    println!("Salt: {}", plan.salt_nonce);
+    for contract in plan.contracts() {
+        println!(
+            "{} | code id: {} | salt: {} | {}",
+            contract.label, contract.code_id, contract.salt, contract.address
+        );
+    }
+}
+
+struct Planner<'a> {
+    label_prefix: &'a str,
+    salt_nonce: &'a str,
+    config: &'a Config,
+    // code checksums are fetched once per code id
+    code_hashes: HashMap<u64, String>,
+}
+
+impl Planner<'_> {
+    fn plan(&mut self, role: &str, code_id: u64) -> Result<PlannedContract> {
+        let code_hash = match self.code_hashes.get(&code_id) {
+            Some(code_hash) => code_hash.clone(),
+            None => {
+                let code_hash = get_code_hash(self.config, code_id)?;
+                self.code_hashes.insert(code_id, code_hash.clone());
+                code_hash
+            }
+        };
+
+        let label = format!("{}_{}", self.label_prefix, role);
+        let salt = generate_salt(&label, self.salt_nonce);
+        let address = predict_contract_address(
+            &self.config.tool_operator_address,
+            &salt,
+            &code_hash,
+            &self.config.bech32_prefix,
+        )?;
+
+        Ok(PlannedContract {
+            label,
+            code_id,
+            salt,
+            address,
+        })
+    }
+
+    fn plan_per_pool(
+        &mut self,
+        role: &str,
+        code_id: u64,
+        pools_count: usize,
+    ) -> Result<Vec<PlannedContract>> {
+        (0..pools_count)
+            .map(|i| self.plan_for_pool(role, code_id, i))
+            .collect()
+    }
+
+    fn plan_for_pool(&mut self, role: &str, code_id: u64, index: usize) -> Result<PlannedContract> {
+        self.plan(&format!("{}_{}", role, index), code_id)
+    }
+}
+
+/// Hex-encoded salt derived from the contract label (label prefix and role) and the nonce,
+/// so every role gets its own address
+fn generate_salt(label: &str, salt_nonce: &str) -> String {
+    format!(
+        "{:x}",
+        Sha256::digest(format!("{}{}", label, salt_nonce).as_bytes())
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn salt_depends_on_label_and_nonce() {
+        let salt = generate_salt("prog_input", "0123456789abcdef");
+        assert_eq!(salt.len(), 64);
+        assert_eq!(salt, generate_salt("prog_input", "0123456789abcdef"));
+        assert_ne!(salt, generate_salt("prog_processor", "0123456789abcdef"));
+        assert_ne!(salt, generate_salt("prog_input", "fedcba9876543210"));
+    }
+
+    #[test]
+    fn salt_nonce_is_sixteen_hex_digits() {
+        let nonce = generate_salt_nonce();
+        assert_eq!(nonce.len(), 16);
+        assert!(nonce.chars().all(|c| c.is_ascii_hexdigit()));
+    }
+}
@@ -0,0 +1,235 @@
+use crate::config::Config;
+use crate::wasm::{
+    execute_wasm_contract_with_tx, find_event_attributes, generate_wasm_execute_msg,
+    query_wasm_smart,
+};
+use anyhow::{anyhow, Error, Result};
+use serde_json::Value;
+
+/// Processor queue priorities, in the order the processor serves them
+pub const QUEUE_PRIORITIES: [&str; 2] = ["high", "medium"];
+
+/// Returns the message batches queued on the processor for the given priority
+pub fn get_queue(
+    config: &Config,
+    processor_address: &str,
+    priority: &str,
+) -> Result<Vec<Value>, Error> {
+    let query_msg = format!(
+        r#"{{"get_queue":{{"from":null,"to":null,"priority":"{}"}}}}"#,
+        priority
+    );
+    let queue = query_wasm_smart(config, processor_address, &query_msg)?;
+
+    Ok(queue.as_array().cloned().unwrap_or_default())
+}
+
+/// Returns the next batch the processor will execute on tick, if any
+pub fn get_next_batch(config: &Config, processor_address: &str) -> Result<Option<Value>, Error> {
+    for priority in QUEUE_PRIORITIES {
+        let query_msg = format!(
+            r#"{{"get_queue":{{"from":0,"to":1,"priority":"{}"}}}}"#,
+            priority
+        );
+        let queue = query_wasm_smart(config, processor_address, &query_msg)?;
+        if let Some(batch) = queue.as_array().and_then(|batches| batches.first()) {
+            return Ok(Some(batch.clone()));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Returns the authorization contract the processor reports execution results to
+pub fn get_processor_authorization(
+    config: &Config,
+    processor_address: &str,
+) -> Result<String, Error> {
+    let processor_config = query_wasm_smart(config, processor_address, r#"{"config":{}}"#)?;
+    processor_config["authorization_contract"]
+        .as_str()
+        .map(String::from)
+        .ok_or_else(|| anyhow!("Authorization contract not found in processor config"))
+}
+
+/// Returns the processor callback of a single execution
+pub fn get_processor_callback(
+    config: &Config,
+    auth_contract_address: &str,
+    execution_id: u64,
+) -> Result<Value, Error> {
+    query_wasm_smart(
+        config,
+        auth_contract_address,
+        &format!(
+            r#"{{"processor_callback":{{"execution_id":{}}}}}"#,
+            execution_id
+        ),
+    )
+}
+
+/// Returns the processor callbacks stored on the authorization contract,
+/// which hold the execution result of every enqueued execution
+pub fn get_processor_callbacks(
+    config: &Config,
+    auth_contract_address: &str,
+) -> Result<Vec<Value>, Error> {
+    let mut callbacks = Vec::new();
+    let mut start_after: Option<u64> = None;
+
+    loop {
+        let query_msg = format!(
+            r#"{{"processor_callbacks":{{"start_after":{},"limit":100}}}}"#,
+            start_after.map_or("null".to_string(), |id| id.to_string())
+        );
+        let page = query_wasm_smart(config, auth_contract_address, &query_msg)?;
+        let page = page.as_array().cloned().unwrap_or_default();
+
+        let last_id = page.last().and_then(|c| c["execution_id"].as_u64());
+        let page_len = page.len();
+        callbacks.extend(page);
+
+        match last_id {
+            Some(id) if page_len == 100 => start_after = Some(id),
+            _ => break,
+        }
+    }
+
+    Ok(callbacks)
+}
+
+/// Execution results are serialized as `"success"`, `"in_process"`, or
+/// `{"rejected": "..."}`, `{"partially_executed": [n, "..."]}`, ...
+pub fn execution_result_name(execution_result: &Value) -> String {
+    match execution_result {
+        Value::String(name) => name.clone(),
+        Value::Object(map) => map.keys().next().cloned().unwrap_or_default(),
+        _ => "unknown".to_string(),
+    }
+}
+
+pub fn is_failed_execution(execution_result: &Value) -> bool {
+    !matches!(
+        execution_result_name(execution_result).as_str(),
+        "success" | "in_process"
+    )
+}
+
+/// Sends a permissionless tick to the processor and returns the indexed transaction
+pub fn tick(config: &Config, processor_address: &str) -> Result<Value, Error> {
+    let tick_msg = valence_processor_utils::msg::ExecuteMsg::PermissionlessAction(
+        valence_processor_utils::msg::PermissionlessMsg::Tick {},
+    );
+
+    execute_wasm_contract_with_tx(
+        processor_address,
+        &serde_json::to_string(&tick_msg)?,
+        config,
+    )
+}
+
+/// Generates a tick of the processor without broadcasting it, with the gas estimated for it
+pub fn generate_tick(config: &Config, processor_address: &str) -> Result<(Value, u64), Error> {
+    let tick_msg = valence_processor_utils::msg::ExecuteMsg::PermissionlessAction(
+        valence_processor_utils::msg::PermissionlessMsg::Tick {},
+    );
+
+    generate_wasm_execute_msg(
+        processor_address,
+        &serde_json::to_string(&tick_msg)?,
+        config,
+    )
+}
+
+/// Ticks the processor once and reports the execution result of the batch it processed.
+/// Returns the execution id of the batch and its execution result, if a batch was queued.
+pub fn tick_and_report(
+    config: &Config,
+    processor_address: &str,
+    auth_contract_address: &str,
+) -> Result<Option<(u64, Value)>, Error> {
+    let next_batch = get_next_batch(config, processor_address)?;
+
+    let tx_data = tick(config, processor_address)?;
+
+    let Some(execution_id) = next_batch.and_then(|batch| batch["id"].as_u64()) else {
+        println!("No batch was queued on the processor");
+        return Ok(None);
+    };
+
+    let callback = get_processor_callback(config, auth_contract_address, execution_id)?;
+    let execution_result = callback["execution_result"].clone();
+    let callback_sent = find_event_attributes(&tx_data, "_contract_address")
+        .iter()
+        .any(|address| address == auth_contract_address);
+
+    println!(
+        "Execution {} ({}): {}{}",
+        execution_id,
+        callback["label"].as_str().unwrap_or_default(),
+        execution_result_name(&execution_result),
+        if callback_sent {
+            ", callback sent to the authorization contract"
+        } else {
+            ""
+        }
+    );
+    if is_failed_execution(&execution_result) {
+        println!("  {}", execution_result);
+    }
+
+    Ok(Some((execution_id, execution_result)))
+}
+
+/// Finds the execution id assigned by the authorization contract to a `send_msgs` transaction.
+/// Falls back to the latest processor callback of the authorization label
+/// if the id is not part of the transaction events.
+pub fn find_execution_id(
+    config: &Config,
+    auth_contract_address: &str,
+    tx_data: &Value,
+    label: &str,
+) -> Result<u64, Error> {
+    if let Some(execution_id) = find_event_attributes(tx_data, "execution_id")
+        .iter()
+        .find_map(|id| id.parse().ok())
+    {
+        return Ok(execution_id);
+    }
+
+    get_processor_callbacks(config, auth_contract_address)?
+        .iter()
+        .filter(|callback| callback["label"].as_str() == Some(label))
+        .filter_map(|callback| callback["execution_id"].as_u64())
+        .max()
+        .ok_or_else(|| anyhow!("Execution id of authorization {} not found", label))
+}
+
+/// Ticks the processor until the execution is no longer in process and returns its execution result
+pub fn wait_for_execution(
+    config: &Config,
+    processor_address: &str,
+    auth_contract_address: &str,
+    execution_id: u64,
+    max_iterations: u32,
+) -> Result<Value, Error> {
+    for _ in 0..max_iterations {
+        let callback = get_processor_callback(config, auth_contract_address, execution_id)?;
+        if execution_result_name(&callback["execution_result"]) != "in_process" {
+            return Ok(callback["execution_result"].clone());
+        }
+
+        tick_and_report(config, processor_address, auth_contract_address)?;
+    }
+
+    let callback = get_processor_callback(config, auth_contract_address, execution_id)?;
+    if execution_result_name(&callback["execution_result"]) != "in_process" {
+        return Ok(callback["execution_result"].clone());
+    }
+
+    Err(anyhow!(
+        "Execution {} still in process after {} ticks",
+        execution_id,
+        max_iterations
+    ))
+}
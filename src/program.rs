@@ -0,0 +1,367 @@
+use crate::address::Address;
+use crate::authorization::{
+    get_function_identifier, Authorization, PROVIDE_DOUBLE_SIDED_LIQUIDITY, SPLIT,
+    WITHDRAW_LIQUIDITY,
+};
+use crate::config::Config;
+use crate::graph::Graph;
+use crate::manifest::ProgramManifest;
+use crate::wasm::{get_authorizations, get_library_config, query_wasm_smart};
+use anyhow::{Error, Result};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+/// Per-pool part of a deployed program, reconstructed from the library configs
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct PoolTopology {
+    pub pool_address: String,
+    pub asset1: String,
+    pub asset2: String,
+    pub pool_type: Value,
+    pub split_output_account: String,
+    pub liquidity_output_account: String,
+    pub astroport_lper_library: String,
+    pub astroport_withdraw_library: Option<String>,
+    pub withdraw_output_address: Option<String>,
+    pub withdrawal_account: Option<String>,
+}
+
+/// Deployed program reconstructed from its authorization contract
+#[derive(Debug, Serialize, Default)]
+pub struct ProgramTopology {
+    pub authorization_address: String,
+    pub processor_address: Option<String>,
+    pub authorization_labels: Vec<String>,
+    pub splitter_library: Option<String>,
+    pub input_account: Option<String>,
+    pub splits: Value,
+    pub pools: Vec<PoolTopology>,
+}
+
+impl ProgramTopology {
+    /// All Valence accounts of the program
+    pub fn accounts(&self) -> Vec<String> {
+        let mut accounts: Vec<String> = self.input_account.iter().cloned().collect();
+        for pool in &self.pools {
+            let pool_accounts = [
+                Some(&pool.split_output_account),
+                Some(&pool.liquidity_output_account),
+                pool.withdrawal_account.as_ref(),
+            ];
+            for account in pool_accounts.into_iter().flatten() {
+                if !accounts.contains(account) {
+                    accounts.push(account.clone());
+                }
+            }
+        }
+        accounts
+    }
+
+    /// Program described by a manifest, without querying the chain. Manifests don't record
+    /// where the withdrawers send the assets, so it is passed in.
+    pub fn from_manifest(manifest: &ProgramManifest, withdraw_output_address: &str) -> Self {
+        let mut splits = Vec::new();
+        let mut pools = Vec::new();
+        for (i, pool) in manifest.pools.iter().enumerate() {
+            let split_output_account = manifest.split_output_accounts[i].to_string();
+            for (denom, amount) in [
+                (&pool.denom_a, pool.amount_a),
+                (&pool.denom_b, pool.amount_b),
+            ] {
+                splits.push(json!({
+                    "denom": { "native": denom },
+                    "account": split_output_account,
+                    "amount": { "fixed_amount": amount.to_string() },
+                }));
+            }
+
+            pools.push(PoolTopology {
+                pool_address: pool.address.to_string(),
+                asset1: pool.denom_a.clone(),
+                asset2: pool.denom_b.clone(),
+                pool_type: serde_json::to_value(&pool.pool_type).unwrap_or_default(),
+                split_output_account,
+                liquidity_output_account: manifest.liquidity_output_accounts[i].to_string(),
+                astroport_lper_library: manifest.astroport_lper_libraries[i].to_string(),
+                astroport_withdraw_library: manifest
+                    .astroport_withdraw_libraries
+                    .get(i)
+                    .map(Address::to_string),
+                withdraw_output_address: Some(withdraw_output_address.to_string()),
+                withdrawal_account: manifest.withdrawal_accounts.get(i).map(Address::to_string),
+            });
+        }
+
+        ProgramTopology {
+            authorization_address: manifest.authorization_address.to_string(),
+            processor_address: Some(manifest.processor_address.to_string()),
+            authorization_labels: Vec::new(),
+            splitter_library: Some(manifest.splitter_library.to_string()),
+            input_account: Some(manifest.input_account.to_string()),
+            splits: Value::Array(splits),
+            pools,
+        }
+    }
+
+    /// Contracts of the program as nodes, with the flow of funds and execution between them as edges
+    pub fn graph(&self) -> Graph {
+        let mut graph = self.flow_graph(&[]);
+        graph.add_node(&self.authorization_address, "authorization");
+        if let Some(processor) = &self.processor_address {
+            graph.add_node(processor, "processor");
+            graph.add_edge(&self.authorization_address, processor, "enqueues");
+            for library in self.libraries() {
+                graph.add_edge(processor, &library, "executes");
+            }
+        }
+
+        graph
+    }
+
+    /// Flow of the funds from the input account to the withdraw destination, with the split
+    /// amounts and the labels of the authorizations that execute every library
+    pub fn flow_graph(&self, authorizations: &[Authorization]) -> Graph {
+        let mut graph = Graph::default();
+        if let Some(splitter) = &self.splitter_library {
+            if let Some(input_account) = &self.input_account {
+                graph.add_node(input_account, "input account");
+                graph.add_edge(input_account, splitter, "split");
+            }
+            graph.add_node(
+                splitter,
+                &library_role("splitter", splitter, authorizations),
+            );
+        }
+
+        for pool in &self.pools {
+            graph.add_node(&pool.split_output_account, "split output account");
+            graph.add_node(
+                &pool.astroport_lper_library,
+                &library_role(
+                    "Astroport LPer",
+                    &pool.astroport_lper_library,
+                    authorizations,
+                ),
+            );
+            graph.add_node(&pool.liquidity_output_account, "liquidity output account");
+            graph.add_node(
+                &pool.pool_address,
+                &format!("pool {} / {}", pool.asset1, pool.asset2),
+            );
+            if let Some(splitter) = &self.splitter_library {
+                graph.add_edge(
+                    splitter,
+                    &pool.split_output_account,
+                    &self.split_amounts(&pool.split_output_account),
+                );
+            }
+            graph.add_edge(
+                &pool.split_output_account,
+                &pool.astroport_lper_library,
+                "provide liquidity",
+            );
+            graph.add_edge(&pool.astroport_lper_library, &pool.pool_address, "");
+            graph.add_edge(
+                &pool.astroport_lper_library,
+                &pool.liquidity_output_account,
+                "LP tokens",
+            );
+
+            if let Some(withdraw_library) = &pool.astroport_withdraw_library {
+                graph.add_node(
+                    withdraw_library,
+                    &library_role("Astroport withdrawer", withdraw_library, authorizations),
+                );
+                graph.add_edge(
+                    &pool.liquidity_output_account,
+                    withdraw_library,
+                    "withdraw liquidity",
+                );
+                graph.add_edge(withdraw_library, &pool.pool_address, "");
+                if let Some(withdraw_output) = &pool.withdraw_output_address {
+                    let role = match &pool.withdrawal_account {
+                        Some(account) if account == withdraw_output => "withdrawal account",
+                        _ => "withdraw output",
+                    };
+                    graph.add_node(withdraw_output, role);
+                    graph.add_edge(
+                        withdraw_library,
+                        withdraw_output,
+                        &format!("{} + {}", pool.asset1, pool.asset2),
+                    );
+                }
+            }
+        }
+
+        graph
+    }
+
+    fn libraries(&self) -> Vec<String> {
+        let mut libraries: Vec<String> = self.splitter_library.iter().cloned().collect();
+        for pool in &self.pools {
+            libraries.push(pool.astroport_lper_library.clone());
+            libraries.extend(pool.astroport_withdraw_library.iter().cloned());
+        }
+        libraries
+    }
+
+    /// Amounts the splitter sends to the account, e.g. `100 untrn, 200 uatom`
+    fn split_amounts(&self, account: &str) -> String {
+        self.splits
+            .as_array()
+            .map(|splits| {
+                splits
+                    .iter()
+                    .filter(|split| split["account"].as_str() == Some(account))
+                    .map(|split| {
+                        let denom = first_value(&split["denom"]);
+                        let amount = first_value(&split["amount"]);
+                        format!("{} {}", amount, denom)
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Role of a library, with the labels of the enabled authorizations calling it
+fn library_role(role: &str, library_address: &str, authorizations: &[Authorization]) -> String {
+    let labels: Vec<&str> = authorizations
+        .iter()
+        .filter(|authorization| {
+            authorization.is_enabled()
+                && authorization
+                    .subroutine
+                    .atomic
+                    .functions
+                    .iter()
+                    .any(|function| function.contract_address().as_deref() == Some(library_address))
+        })
+        .map(|authorization| authorization.label.as_str())
+        .collect();
+
+    if labels.is_empty() {
+        role.to_string()
+    } else {
+        format!("{}\n{}", role, labels.join(", "))
+    }
+}
+
+/// Value of a single-key object such as `{"native": "untrn"}` or `{"fixed_amount": "100"}`
+fn first_value(value: &Value) -> String {
+    match value {
+        Value::Object(map) => map.values().next().map(compact_value).unwrap_or_default(),
+        _ => compact_value(value),
+    }
+}
+
+fn compact_value(value: &Value) -> String {
+    value
+        .as_str()
+        .map(String::from)
+        .unwrap_or_else(|| value.to_string())
+}
+
+/// Walks the chain from the authorization contract: reads the library address of every
+/// authorized function, then the config of every library to find accounts and pools.
+/// Withdrawal accounts aren't referenced by any library, so they are only known from a manifest.
+pub fn discover_program(
+    config: &Config,
+    auth_contract_address: &str,
+    manifest: Option<&ProgramManifest>,
+) -> Result<(ProgramTopology, Vec<Authorization>), Error> {
+    let authorizations = get_authorizations(config, auth_contract_address)?;
+
+    let mut topology = ProgramTopology {
+        authorization_address: auth_contract_address.to_string(),
+        authorization_labels: authorizations.iter().map(|a| a.label.clone()).collect(),
+        ..Default::default()
+    };
+
+    let mut lper_libraries = Vec::new();
+    let mut withdraw_libraries = Vec::new();
+    // Disabled authorizations may still reference libraries of removed pools
+    for authorization in authorizations.iter().filter(|a| a.is_enabled()) {
+        for function in &authorization.subroutine.atomic.functions {
+            let (Some(identifier), Some(library_address)) = (
+                get_function_identifier(function),
+                function.contract_address(),
+            ) else {
+                continue;
+            };
+
+            match identifier.as_str() {
+                SPLIT => topology.splitter_library = Some(library_address),
+                PROVIDE_DOUBLE_SIDED_LIQUIDITY if !lper_libraries.contains(&library_address) => {
+                    lper_libraries.push(library_address)
+                }
+                WITHDRAW_LIQUIDITY if !withdraw_libraries.contains(&library_address) => {
+                    withdraw_libraries.push(library_address)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    if let Some(splitter_library) = &topology.splitter_library {
+        let splitter_config = get_library_config(config, splitter_library)?;
+        topology.input_account = splitter_config["input_addr"].as_str().map(String::from);
+        topology.splits = splitter_config["splits"].clone();
+        topology.processor_address = get_library_processor(config, splitter_library)?;
+    }
+
+    for lper_library in &lper_libraries {
+        let lper_config = get_library_config(config, lper_library)?;
+        if topology.processor_address.is_none() {
+            topology.processor_address = get_library_processor(config, lper_library)?;
+        }
+
+        topology.pools.push(PoolTopology {
+            pool_address: value_to_string(&lper_config["pool_addr"]),
+            asset1: value_to_string(&lper_config["lp_config"]["asset_data"]["asset1"]),
+            asset2: value_to_string(&lper_config["lp_config"]["asset_data"]["asset2"]),
+            pool_type: lper_config["lp_config"]["pool_type"].clone(),
+            split_output_account: value_to_string(&lper_config["input_addr"]),
+            liquidity_output_account: value_to_string(&lper_config["output_addr"]),
+            astroport_lper_library: lper_library.clone(),
+            ..Default::default()
+        });
+    }
+
+    for withdraw_library in &withdraw_libraries {
+        let withdraw_config = get_library_config(config, withdraw_library)?;
+        let input_account = value_to_string(&withdraw_config["input_addr"]);
+
+        if let Some(pool) = topology
+            .pools
+            .iter_mut()
+            .find(|pool| pool.liquidity_output_account == input_account)
+        {
+            pool.astroport_withdraw_library = Some(withdraw_library.clone());
+            pool.withdraw_output_address =
+                withdraw_config["output_addr"].as_str().map(String::from);
+        }
+    }
+
+    if let Some(manifest) = manifest {
+        for pool in topology.pools.iter_mut() {
+            pool.withdrawal_account = manifest
+                .liquidity_output_accounts
+                .iter()
+                .position(|account| account.as_str() == pool.liquidity_output_account)
+                .and_then(|i| manifest.withdrawal_accounts.get(i).map(Address::to_string));
+        }
+    }
+
+    Ok((topology, authorizations))
+}
+
+pub fn get_library_processor(config: &Config, library_address: &str) -> Result<Option<String>> {
+    let processor = query_wasm_smart(config, library_address, r#"{"get_processor":{}}"#)?;
+    Ok(processor.as_str().map(String::from))
+}
+
+fn value_to_string(value: &Value) -> String {
+    value.as_str().unwrap_or_default().to_string()
+}
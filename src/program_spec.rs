@@ -0,0 +1,160 @@
+use crate::address::Address;
+use crate::config::Config;
+use anyhow::{Context, Error, Result};
+use cosmwasm_std::Uint128;
+use cw_utils::Expiration;
+use serde::Deserialize;
+use std::fs;
+use valence_authorization_utils::authorization::{
+    AuthorizationDuration, AuthorizationModeInfo, PermissionTypeInfo, Priority,
+};
+
+/// Program specification loaded from a JSON file, e.g.
+/// ```json
+/// {
+///   "authorizations": {
+///     "deploy": { "mode": { "permissioned": { "addresses": ["neutron1..."], "call_limit": 1 } } },
+///     "withdraw": { "mode": { "permissioned": { "addresses": ["neutron1...", "neutron1..."] } } }
+///   }
+/// }
+/// ```
+#[derive(Debug, Deserialize, Default)]
+pub struct ProgramSpec {
+    #[serde(default)]
+    pub authorizations: AuthorizationsSpec,
+}
+
+/// Settings for each kind of authorization created by the tool.
+/// Missing entries fall back to the default authorization settings.
+#[derive(Debug, Deserialize, Default)]
+pub struct AuthorizationsSpec {
+    #[serde(default)]
+    pub deploy: AuthorizationSpec,
+    #[serde(default)]
+    pub withdraw: AuthorizationSpec,
+    /// Per-pool deploy, which only provides liquidity: the splitter is shared by the pools,
+    /// so the funds are split by the program-wide deploy
+    #[serde(default)]
+    pub pool_deploy: AuthorizationSpec,
+    #[serde(default)]
+    pub pool_withdraw: AuthorizationSpec,
+}
+
+#[derive(Debug, Deserialize, Default, Clone)]
+pub struct AuthorizationSpec {
+    #[serde(default)]
+    pub mode: AuthorizationModeSpec,
+    /// Authorization can't be executed before this time or height
+    pub not_before: Option<Expiration>,
+    /// How long the authorization stays valid after creation (sets its expiration)
+    pub duration: Option<AuthorizationDuration>,
+    pub max_concurrent_executions: Option<u64>,
+    pub priority: Option<Priority>,
+}
+
+#[derive(Debug, Deserialize, Default, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthorizationModeSpec {
+    /// Permissioned for the tool operator only, without a call limit
+    #[default]
+    Operator,
+    Permissionless,
+    Permissioned {
+        addresses: Vec<Address>,
+        call_limit: Option<u128>,
+    },
+}
+
+impl AuthorizationModeSpec {
+    pub fn to_mode_info(&self, config: &Config) -> AuthorizationModeInfo {
+        match self {
+            AuthorizationModeSpec::Operator => {
+                AuthorizationModeInfo::Permissioned(PermissionTypeInfo::WithoutCallLimit(vec![
+                    config.tool_operator_address.to_string(),
+                ]))
+            }
+            AuthorizationModeSpec::Permissionless => AuthorizationModeInfo::Permissionless,
+            AuthorizationModeSpec::Permissioned {
+                addresses,
+                call_limit: None,
+            } => AuthorizationModeInfo::Permissioned(PermissionTypeInfo::WithoutCallLimit(
+                addresses.iter().map(ToString::to_string).collect(),
+            )),
+            AuthorizationModeSpec::Permissioned {
+                addresses,
+                call_limit: Some(call_limit),
+            } => AuthorizationModeInfo::Permissioned(PermissionTypeInfo::WithCallLimit(
+                addresses
+                    .iter()
+                    .map(|address| (address.to_string(), Uint128::from(*call_limit)))
+                    .collect(),
+            )),
+        }
+    }
+}
+
+pub fn load_program_spec(path: Option<&str>, bech32_prefix: &str) -> Result<ProgramSpec> {
+    let spec: ProgramSpec = match path {
+        Some(path) => {
+            let content = fs::read_to_string(path)
+                .with_context(|| format!("Failed to read program spec {}", path))?;
+            serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse program spec {}", path))?
+        }
+        None => ProgramSpec::default(),
+    };
+
+    for (name, authorization) in [
+        ("deploy", &spec.authorizations.deploy),
+        ("withdraw", &spec.authorizations.withdraw),
+        ("pool_deploy", &spec.authorizations.pool_deploy),
+        ("pool_withdraw", &spec.authorizations.pool_withdraw),
+    ] {
+        validate_authorization_spec(name, authorization)?;
+        if let AuthorizationModeSpec::Permissioned { addresses, .. } = &authorization.mode {
+            for address in addresses {
+                address
+                    .validate(bech32_prefix)
+                    .with_context(|| format!("Invalid {} authorization address", name))?;
+            }
+        }
+    }
+
+    Ok(spec)
+}
+
+fn validate_authorization_spec(name: &str, spec: &AuthorizationSpec) -> Result<(), Error> {
+    validate_mode_spec(name, &spec.mode)?;
+
+    if spec.max_concurrent_executions == Some(0) {
+        return Err(Error::msg(format!(
+            "max_concurrent_executions of the {} authorization must be greater than 0",
+            name
+        )));
+    }
+
+    Ok(())
+}
+
+pub fn validate_mode_spec(name: &str, mode: &AuthorizationModeSpec) -> Result<(), Error> {
+    if let AuthorizationModeSpec::Permissioned {
+        addresses,
+        call_limit,
+    } = mode
+    {
+        if addresses.is_empty() {
+            return Err(Error::msg(format!(
+                "Permissioned {} authorization needs at least one address",
+                name
+            )));
+        }
+        if *call_limit == Some(0) {
+            return Err(Error::msg(format!(
+                "Call limit of the {} authorization must be greater than 0",
+                name
+            )));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,155 @@
+use crate::address::Address;
+use crate::config::Config;
+use crate::dao::build_wasm_execute_msg;
+use crate::helpers::build_accept_ownership_msg;
+use crate::manifest::ProgramManifest;
+use crate::wasm::{get_authorizations, get_code_hash, get_contract_code_id};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Renders the markdown governance proposal asking the DAO committee to accept ownership of
+/// the program, with the contracts, pools and authorizations read from the chain
+pub fn render_program_proposal(config: &Config, manifest: &ProgramManifest) -> Result<String> {
+    let mut md = String::new();
+    writeln!(
+        md,
+        "# Accept ownership of liquidity deployment program {}\n",
+        manifest.label_prefix
+    )?;
+    writeln!(
+        md,
+        "This proposal accepts ownership of the authorization contract and the Valence accounts \
+        of program `{}`. The program splits the capital of its input account over {} Astroport \
+        pool(s), provides liquidity to them and withdraws it through authorizations executed by \
+        its processor. The libraries are owned by the DAO committee since their instantiation.\n",
+        manifest.label_prefix,
+        manifest.pools.len()
+    )?;
+
+    writeln!(md, "## Contracts\n")?;
+    writeln!(md, "| Contract | Address | Code ID | Checksum |")?;
+    writeln!(md, "| --- | --- | --- | --- |")?;
+    // code checksums are fetched once per code id
+    let mut code_hashes: HashMap<u64, String> = HashMap::new();
+    for (role, address) in program_contracts(manifest) {
+        let code_id = get_contract_code_id(config, &address)?;
+        let code_hash = match code_hashes.get(&code_id) {
+            Some(code_hash) => code_hash.clone(),
+            None => {
+                let code_hash = get_code_hash(config, code_id)?;
+                code_hashes.insert(code_id, code_hash.clone());
+                code_hash
+            }
+        };
+        writeln!(
+            md,
+            "| {} | `{}` | {} | `{}` |",
+            role, address, code_id, code_hash
+        )?;
+    }
+
+    writeln!(md, "\n## Pools\n")?;
+    writeln!(md, "| Pool | Address | Amount A | Amount B | Pool type |")?;
+    writeln!(md, "| --- | --- | --- | --- | --- |")?;
+    for pool in &manifest.pools {
+        writeln!(
+            md,
+            "| {} | `{}` | {} {} | {} {} | `{}` |",
+            pool.label_id(),
+            pool.address,
+            pool.amount_a,
+            pool.denom_a,
+            pool.amount_b,
+            pool.denom_b,
+            serde_json::to_string(&pool.pool_type)?
+        )?;
+    }
+
+    writeln!(md, "\n## Authorizations\n")?;
+    writeln!(md, "| Label | Mode | State |")?;
+    writeln!(md, "| --- | --- | --- |")?;
+    for authorization in get_authorizations(config, &manifest.authorization_address)? {
+        writeln!(
+            md,
+            "| {} | `{}` | {} |",
+            authorization.label,
+            authorization.mode,
+            if authorization.is_enabled() {
+                "enabled"
+            } else {
+                "disabled"
+            }
+        )?;
+    }
+
+    let mut accept_contracts = vec![manifest.authorization_address.clone()];
+    accept_contracts.extend(program_accounts(manifest));
+    let accept_msgs = accept_contracts
+        .iter()
+        .map(|contract_address| {
+            build_wasm_execute_msg(&build_accept_ownership_msg(contract_address)?)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    writeln!(md, "\n## Messages\n")?;
+    writeln!(
+        md,
+        "`accept_ownership` of the authorization contract and the {} accounts:\n",
+        accept_contracts.len() - 1
+    )?;
+    writeln!(
+        md,
+        "```json\n{}\n```",
+        serde_json::to_string_pretty(&accept_msgs)?
+    )?;
+
+    Ok(md)
+}
+
+/// Every contract of the program with its role, in deployment order
+fn program_contracts(manifest: &ProgramManifest) -> Vec<(String, Address)> {
+    let mut contracts = vec![
+        (
+            "Authorization".to_string(),
+            manifest.authorization_address.clone(),
+        ),
+        ("Processor".to_string(), manifest.processor_address.clone()),
+        ("Input account".to_string(), manifest.input_account.clone()),
+        (
+            "Splitter library".to_string(),
+            manifest.splitter_library.clone(),
+        ),
+    ];
+
+    for (i, pool) in manifest.pools.iter().enumerate() {
+        let pool_contracts = [
+            ("split output account", &manifest.split_output_accounts),
+            (
+                "liquidity output account",
+                &manifest.liquidity_output_accounts,
+            ),
+            ("withdrawal account", &manifest.withdrawal_accounts),
+            ("Astroport LPer library", &manifest.astroport_lper_libraries),
+            (
+                "Astroport withdrawer library",
+                &manifest.astroport_withdraw_libraries,
+            ),
+        ];
+        for (role, addresses) in pool_contracts {
+            if let Some(address) = addresses.get(i) {
+                contracts.push((format!("{} {}", pool.label_id(), role), address.clone()));
+            }
+        }
+    }
+
+    contracts
+}
+
+fn program_accounts(manifest: &ProgramManifest) -> Vec<Address> {
+    let mut accounts = vec![manifest.input_account.clone()];
+    accounts.extend(manifest.split_output_accounts.iter().cloned());
+    accounts.extend(manifest.liquidity_output_accounts.iter().cloned());
+    accounts.extend(manifest.withdrawal_accounts.iter().cloned());
+    accounts
+}
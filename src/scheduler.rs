@@ -0,0 +1,442 @@
+use crate::cli::ProgramAction;
+use crate::config::Config;
+use crate::handlers::execute_program;
+use crate::helpers::get_filtered_authorizations;
+use crate::wasm::get_latest_block;
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, TimeZone, Utc};
+use cosmwasm_std::Timestamp;
+use cron::Schedule;
+use cw_utils::Expiration;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::path::Path;
+use std::str::FromStr;
+use std::thread::sleep;
+use std::time::Duration;
+
+const LOCK_ATTEMPTS: u32 = 100;
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduleTrigger {
+    /// Run once at the given Unix timestamp
+    At { timestamp: i64 },
+    /// Run repeatedly on a cron expression (with seconds, e.g. `0 0 12 * * Fri *`)
+    Cron { expression: String },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScheduledExecution {
+    pub id: u64,
+    pub auth_contract_address: String,
+    pub action: ProgramAction,
+    pub pool: Option<String>,
+    pub trigger: ScheduleTrigger,
+    pub wait: bool,
+    pub next_run: Option<i64>,
+    pub last_run: Option<i64>,
+    pub last_error: Option<String>,
+    /// Failed runs of a one-off execution since it was due
+    #[serde(default)]
+    pub failed_attempts: u32,
+    /// On-chain window of the authorizations, checked before running when it was only proposed
+    #[serde(default)]
+    pub sync_window: Option<SyncWindow>,
+}
+
+/// not_before and expiration (Unix timestamps) set on the authorizations around a one-off run
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct SyncWindow {
+    pub not_before: i64,
+    pub expiration: i64,
+}
+
+impl SyncWindow {
+    pub fn not_before(&self) -> Expiration {
+        Expiration::AtTime(Timestamp::from_seconds(self.not_before as u64))
+    }
+
+    pub fn expiration(&self) -> Expiration {
+        Expiration::AtTime(Timestamp::from_seconds(self.expiration as u64))
+    }
+}
+
+/// Scheduled executions, persisted to a local file so they survive restarts
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct ScheduleState {
+    pub next_id: u64,
+    pub executions: Vec<ScheduledExecution>,
+}
+
+pub fn load_schedule(path: &str) -> Result<ScheduleState> {
+    if !Path::new(path).exists() {
+        return Ok(ScheduleState::default());
+    }
+
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read schedule {}", path))?;
+    serde_json::from_str(&content).with_context(|| format!("Failed to parse schedule {}", path))
+}
+
+/// Loads the schedule, applies the update and saves it while holding the lock file, so the
+/// scheduler and `schedule` commands of other processes don't overwrite each other's changes
+pub fn update_schedule<T>(
+    path: &str,
+    update: impl FnOnce(&mut ScheduleState) -> Result<T>,
+) -> Result<T> {
+    let _lock = ScheduleLock::acquire(path)?;
+    let mut state = load_schedule(path)?;
+    let result = update(&mut state)?;
+    save_schedule(path, &state)?;
+    Ok(result)
+}
+
+fn save_schedule(path: &str, state: &ScheduleState) -> Result<()> {
+    // Write to a temporary file first so an interrupted write doesn't corrupt the schedule
+    let temp_path = format!("{}.tmp", path);
+    fs::write(&temp_path, serde_json::to_string_pretty(state)?)
+        .with_context(|| format!("Failed to write schedule {}", temp_path))?;
+    fs::rename(&temp_path, path).with_context(|| format!("Failed to write schedule {}", path))?;
+    Ok(())
+}
+
+/// Lock file next to the schedule, removed when dropped
+struct ScheduleLock {
+    path: String,
+}
+
+impl ScheduleLock {
+    fn acquire(schedule_path: &str) -> Result<Self> {
+        let path = format!("{}.lock", schedule_path);
+        for _ in 0..LOCK_ATTEMPTS {
+            if OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+                .is_ok()
+            {
+                return Ok(ScheduleLock { path });
+            }
+            sleep(LOCK_RETRY_INTERVAL);
+        }
+
+        Err(anyhow!(
+            "Schedule {} is locked by another process, remove {} if no other process is running",
+            schedule_path,
+            path
+        ))
+    }
+}
+
+impl Drop for ScheduleLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Parses an RFC 3339 date or a Unix timestamp in seconds
+pub fn parse_timestamp(s: &str) -> Result<i64, String> {
+    if let Ok(timestamp) = s.parse::<i64>() {
+        return Ok(timestamp);
+    }
+
+    DateTime::parse_from_rfc3339(s)
+        .map(|date| date.timestamp())
+        .map_err(|_| "Invalid format. Expected an RFC 3339 date or a Unix timestamp".to_string())
+}
+
+/// Returns the next run of the trigger strictly after the given timestamp
+pub fn next_run_after(trigger: &ScheduleTrigger, after: i64) -> Result<Option<i64>> {
+    match trigger {
+        ScheduleTrigger::At { timestamp } => Ok((*timestamp > after).then_some(*timestamp)),
+        ScheduleTrigger::Cron { expression } => {
+            let schedule = Schedule::from_str(expression)
+                .map_err(|e| anyhow!("Invalid cron expression {}: {}", expression, e))?;
+            let after = Utc
+                .timestamp_opt(after, 0)
+                .single()
+                .ok_or_else(|| anyhow!("Invalid timestamp {}", after))?;
+            Ok(schedule.after(&after).next().map(|date| date.timestamp()))
+        }
+    }
+}
+
+/// Sets the next run after a run of the execution. A failed one-off execution is retried
+/// after the delay until it failed `max_retries` more times, a cron execution waits for its next run.
+pub fn reschedule(
+    execution: &mut ScheduledExecution,
+    error: Option<String>,
+    now: i64,
+    retry_delay: i64,
+    max_retries: u32,
+) -> Result<()> {
+    execution.last_run = Some(now);
+    execution.next_run = next_run_after(&execution.trigger, now)?;
+    match &error {
+        Some(_) => {
+            execution.failed_attempts += 1;
+            if matches!(execution.trigger, ScheduleTrigger::At { .. })
+                && execution.failed_attempts <= max_retries
+            {
+                execution.next_run = Some(now + retry_delay);
+            }
+        }
+        None => execution.failed_attempts = 0,
+    }
+    execution.last_error = error;
+
+    Ok(())
+}
+
+/// Runs the due executions of the schedule file until interrupted. Failures of an
+/// iteration, e.g. an unreachable node, are reported and retried on the next poll.
+pub fn run_scheduler(
+    schedule_file: &str,
+    poll_interval: Duration,
+    max_iterations: u32,
+    max_retries: u32,
+    config: &Config,
+) -> Result<()> {
+    println!("Running scheduled executions from {} ...", schedule_file);
+
+    loop {
+        if let Err(e) = run_due_executions(
+            schedule_file,
+            poll_interval,
+            max_iterations,
+            max_retries,
+            config,
+        ) {
+            println!(
+                "Running scheduled executions failed, retrying in {}s: {}",
+                poll_interval.as_secs(),
+                e
+            );
+        }
+
+        sleep(poll_interval);
+    }
+}
+
+fn run_due_executions(
+    schedule_file: &str,
+    poll_interval: Duration,
+    max_iterations: u32,
+    max_retries: u32,
+    config: &Config,
+) -> Result<()> {
+    let now = Utc::now().timestamp();
+    let is_due = |execution: &ScheduledExecution| execution.next_run.is_some_and(|run| run <= now);
+    let due_ids: Vec<u64> = load_schedule(schedule_file)?
+        .executions
+        .iter()
+        .filter(|execution| is_due(execution))
+        .map(|execution| execution.id)
+        .collect();
+    if due_ids.is_empty() {
+        return Ok(());
+    }
+
+    // Block time lags behind the clock, and authorizations synced to the schedule can't be
+    // executed before their not_before, so executions wait for the chain to reach their time
+    let block_time = get_latest_block(config)?.1.seconds() as i64;
+
+    for id in due_ids {
+        // Earlier runs may have taken minutes, the execution may have been removed meanwhile
+        let Some(execution) = load_schedule(schedule_file)?
+            .executions
+            .into_iter()
+            .find(|execution| execution.id == id && is_due(execution))
+        else {
+            continue;
+        };
+        if execution.next_run.is_some_and(|run| run > block_time) {
+            continue;
+        }
+
+        let result = match execution.sync_window {
+            Some(window) if !window_is_set(config, &execution, &window)? => {
+                if now < window.expiration {
+                    println!(
+                        "Scheduled execution {} waits for its authorization window to be set on chain",
+                        id
+                    );
+                    continue;
+                }
+                Err(anyhow!(
+                    "The authorization window was not set on chain before it ended"
+                ))
+            }
+            _ => {
+                println!(
+                    "Running scheduled {:?} {} of {} ...",
+                    execution.action, id, execution.auth_contract_address
+                );
+                execute_program(
+                    &execution.auth_contract_address,
+                    execution.action.clone(),
+                    execution.pool.as_deref(),
+                    execution.wait,
+                    max_iterations,
+                    config,
+                )
+            }
+        };
+
+        let error = result.err().map(|e| {
+            println!("Scheduled execution {} failed: {}", id, e);
+            e.to_string()
+        });
+        // Persist after every run so a restart doesn't run it twice. Only this execution is
+        // updated, changes made by other processes in the meantime are kept.
+        update_schedule(schedule_file, |state| {
+            if let Some(scheduled) = state.executions.iter_mut().find(|e| e.id == id) {
+                reschedule(
+                    scheduled,
+                    error,
+                    now,
+                    poll_interval.as_secs() as i64,
+                    max_retries,
+                )?;
+            }
+            Ok(())
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Whether the executed authorizations have the window on chain, which is not the case
+/// while the proposal setting it hasn't been executed
+fn window_is_set(
+    config: &Config,
+    execution: &ScheduledExecution,
+    window: &SyncWindow,
+) -> Result<bool> {
+    let not_before = serde_json::to_value(window.not_before())?;
+    let expiration = serde_json::to_value(window.expiration())?;
+    let authorizations = get_filtered_authorizations(
+        &execution.auth_contract_address,
+        execution.action.clone(),
+        execution.pool.as_deref(),
+        config,
+    )?;
+
+    Ok(authorizations.iter().all(|authorization| {
+        authorization.not_before == not_before && authorization.expiration == expiration
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn execution(trigger: ScheduleTrigger) -> ScheduledExecution {
+        ScheduledExecution {
+            id: 0,
+            auth_contract_address: "auth".to_string(),
+            action: ProgramAction::Deploy,
+            pool: None,
+            trigger,
+            wait: false,
+            next_run: None,
+            last_run: None,
+            last_error: None,
+            failed_attempts: 0,
+            sync_window: None,
+        }
+    }
+
+    #[test]
+    fn parse_timestamp_formats() {
+        assert_eq!(parse_timestamp("1700000000"), Ok(1_700_000_000));
+        assert_eq!(parse_timestamp("2023-11-14T22:13:20Z"), Ok(1_700_000_000));
+        assert_eq!(
+            parse_timestamp("2023-11-14T23:13:20+01:00"),
+            Ok(1_700_000_000)
+        );
+        assert!(parse_timestamp("tomorrow").is_err());
+    }
+
+    #[test]
+    fn next_run_of_one_off_trigger() {
+        let trigger = ScheduleTrigger::At { timestamp: 100 };
+        assert_eq!(next_run_after(&trigger, 99).unwrap(), Some(100));
+        assert_eq!(next_run_after(&trigger, 100).unwrap(), None);
+    }
+
+    #[test]
+    fn next_run_of_cron_trigger() {
+        // every day at 12:00:00
+        let trigger = ScheduleTrigger::Cron {
+            expression: "0 0 12 * * * *".to_string(),
+        };
+        // 2023-11-14T22:13:20Z
+        assert_eq!(
+            next_run_after(&trigger, 1_700_000_000).unwrap(),
+            Some(1_700_049_600)
+        );
+        assert!(next_run_after(
+            &ScheduleTrigger::Cron {
+                expression: "not cron".to_string()
+            },
+            0
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn failed_one_off_execution_is_retried_until_max_retries() {
+        let mut scheduled = execution(ScheduleTrigger::At { timestamp: 100 });
+        for attempt in 1..=2 {
+            reschedule(&mut scheduled, Some("failed".to_string()), 100, 30, 2).unwrap();
+            assert_eq!(scheduled.failed_attempts, attempt);
+            assert_eq!(scheduled.next_run, Some(130));
+        }
+
+        reschedule(&mut scheduled, Some("failed".to_string()), 100, 30, 2).unwrap();
+        assert_eq!(scheduled.next_run, None);
+        assert_eq!(scheduled.last_error.as_deref(), Some("failed"));
+    }
+
+    #[test]
+    fn successful_run_resets_failed_attempts() {
+        let mut scheduled = execution(ScheduleTrigger::At { timestamp: 100 });
+        reschedule(&mut scheduled, Some("failed".to_string()), 100, 30, 2).unwrap();
+        reschedule(&mut scheduled, None, 130, 30, 2).unwrap();
+        assert_eq!(scheduled.failed_attempts, 0);
+        assert_eq!(scheduled.next_run, None);
+        assert_eq!(scheduled.last_error, None);
+    }
+
+    #[test]
+    fn update_schedule_keeps_other_changes_and_releases_the_lock() {
+        let path = std::env::temp_dir()
+            .join(format!("ld_tool_schedule_test_{}.json", std::process::id()))
+            .display()
+            .to_string();
+        let add = |state: &mut ScheduleState| {
+            let mut scheduled = execution(ScheduleTrigger::At { timestamp: 100 });
+            scheduled.id = state.next_id;
+            state.next_id += 1;
+            state.executions.push(scheduled);
+            Ok(())
+        };
+        update_schedule(&path, add).unwrap();
+        update_schedule(&path, add).unwrap();
+
+        update_schedule(&path, |state| {
+            let scheduled = state.executions.iter_mut().find(|e| e.id == 1).unwrap();
+            reschedule(scheduled, None, 100, 30, 2)
+        })
+        .unwrap();
+
+        let state = load_schedule(&path).unwrap();
+        assert_eq!(state.executions.len(), 2);
+        assert_eq!(state.executions[1].last_run, Some(100));
+        assert!(!Path::new(&format!("{}.lock", path)).exists());
+        fs::remove_file(&path).unwrap();
+    }
+}
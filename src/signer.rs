@@ -0,0 +1,246 @@
+use crate::config::{Config, SignerKind};
+use crate::node_cmd::{build_sign_flags, run_command_with_input, run_tx_command};
+use anyhow::{anyhow, Context, Result};
+use serde_json::Value;
+use std::env::{self, temp_dir};
+use std::fs::{self, DirBuilder};
+use std::os::unix::fs::DirBuilderExt;
+use std::path::{Path, PathBuf};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+// name of the operator key in the throwaway keyring of the mnemonic signer
+const MNEMONIC_KEY_NAME: &str = "operator";
+const OFFLINE_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Signs transactions generated with `--generate-only`
+pub trait Signer {
+    /// Signs the unsigned transaction file and returns the signed transaction as JSON
+    fn sign(&self, unsigned_tx_path: &Path) -> Result<String>;
+}
+
+pub fn build_signer(config: &Config) -> Result<Box<dyn Signer + '_>> {
+    Ok(match config.signer {
+        SignerKind::Keyring => Box::new(KeyringSigner { config }),
+        SignerKind::Mnemonic => Box::new(MnemonicSigner {
+            config,
+            mnemonic: load_mnemonic()?,
+        }),
+        SignerKind::Offline => Box::new(OfflineSigner {
+            config,
+            dir: config
+                .offline_dir
+                .as_deref()
+                .ok_or_else(|| anyhow!("The offline signer needs --offline-dir"))?,
+        }),
+    })
+}
+
+/// Signs with the operator key of the configured keyring backend
+struct KeyringSigner<'a> {
+    config: &'a Config,
+}
+
+impl Signer for KeyringSigner<'_> {
+    fn sign(&self, unsigned_tx_path: &Path) -> Result<String> {
+        let cmd = format!(
+            "{} tx sign {} {}",
+            self.config.neutron_binary,
+            unsigned_tx_path.display(),
+            build_sign_flags(
+                self.config,
+                &self.config.tool_operator_moniker,
+                &self.config.keyring_backend,
+                &self.config.home
+            )
+        );
+        run_tx_command(&cmd, self.config)
+    }
+}
+
+/// Signs with a mnemonic that is never stored in a persistent keyring.
+/// The key only lives in a temporary `file` keyring, encrypted with a random passphrase
+/// that is never written anywhere, for the duration of the signing.
+struct MnemonicSigner<'a> {
+    config: &'a Config,
+    mnemonic: String,
+}
+
+impl Signer for MnemonicSigner<'_> {
+    fn sign(&self, unsigned_tx_path: &Path) -> Result<String> {
+        let keyring = TempKeyring::create()?;
+        let passphrase = format!("{:032x}", rand::random::<u128>());
+        let keyring_home = keyring.path.display().to_string();
+
+        // The new keyring asks for its passphrase twice before the mnemonic is read
+        let recover_cmd = format!(
+            "{} keys add {} --recover --keyring-backend=file --home {} --output=json",
+            self.config.neutron_binary, MNEMONIC_KEY_NAME, keyring_home
+        );
+        let key: Value = serde_json::from_str(&run_command_with_input(
+            &recover_cmd,
+            Some(&format!("{0}\n{0}\n{1}\n", passphrase, self.mnemonic)),
+        )?)?;
+        let key_address = key["address"].as_str().unwrap_or_default();
+        if key_address != self.config.tool_operator_address.as_str() {
+            return Err(anyhow!(
+                "Mnemonic belongs to {}, not to the operator address {}",
+                key_address,
+                self.config.tool_operator_address
+            ));
+        }
+
+        let sign_cmd = format!(
+            "{} tx sign {} {}",
+            self.config.neutron_binary,
+            unsigned_tx_path.display(),
+            build_sign_flags(self.config, MNEMONIC_KEY_NAME, "file", &keyring_home)
+        );
+        run_command_with_input(&sign_cmd, Some(&format!("{}\n", passphrase).repeat(3)))
+    }
+}
+
+/// Keyring directory readable by the current user only, removed when dropped so it
+/// is cleaned up on errors and panics too
+struct TempKeyring {
+    path: PathBuf,
+}
+
+impl TempKeyring {
+    fn create() -> Result<Self> {
+        let path = temp_dir().join(format!("ld_tool_keyring_{:016x}", rand::random::<u64>()));
+        DirBuilder::new()
+            .mode(0o700)
+            .create(&path)
+            .with_context(|| format!("Failed to create keyring directory {}", path.display()))?;
+        Ok(TempKeyring { path })
+    }
+}
+
+impl Drop for TempKeyring {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// Leaves the signing to an offline machine: writes the unsigned transaction to the offline
+/// directory and waits, up to the configured timeout, for the signed transaction to be
+/// saved beside it
+struct OfflineSigner<'a> {
+    config: &'a Config,
+    dir: &'a Path,
+}
+
+impl Signer for OfflineSigner<'_> {
+    fn sign(&self, unsigned_tx_path: &Path) -> Result<String> {
+        let file_name = unsigned_tx_path
+            .file_name()
+            .ok_or_else(|| anyhow!("Invalid transaction file {}", unsigned_tx_path.display()))?
+            .to_string_lossy()
+            .to_string();
+        let unsigned_path = self
+            .dir
+            .join(format!("unsigned_{}", file_name))
+            .display()
+            .to_string();
+        let signed_path = self
+            .dir
+            .join(format!("signed_{}", file_name))
+            .display()
+            .to_string();
+        fs::copy(unsigned_tx_path, &unsigned_path)
+            .with_context(|| format!("Failed to write {}", unsigned_path))?;
+
+        println!(
+            "Sign {} offline and move the result to {}, e.g.:\n  {} tx sign {} --from <key> --chain-id={} --offline --account-number <n> --sequence <n> --output-document {}.tmp && mv {}.tmp {}",
+            unsigned_path,
+            signed_path,
+            self.config.neutron_binary,
+            unsigned_path,
+            self.config.neutron_chain_id,
+            signed_path,
+            signed_path,
+            signed_path
+        );
+        println!(
+            "Waiting up to {}s for {} ...",
+            self.config.offline_sign_timeout.as_secs(),
+            signed_path
+        );
+        let started = Instant::now();
+        let signed_tx = loop {
+            // A file that is still being written doesn't parse yet, so it is read again later
+            if let Some(signed_tx) = read_signed_tx(&signed_path) {
+                break signed_tx;
+            }
+            if started.elapsed() >= self.config.offline_sign_timeout {
+                return Err(anyhow!(
+                    "No signed transaction at {} after {}s, {} is left for signing",
+                    signed_path,
+                    self.config.offline_sign_timeout.as_secs(),
+                    unsigned_path
+                ));
+            }
+            sleep(OFFLINE_POLL_INTERVAL);
+        };
+
+        fs::remove_file(&unsigned_path)?;
+        fs::remove_file(&signed_path)?;
+        Ok(signed_tx)
+    }
+}
+
+/// Returns the signed transaction once the file holds a complete transaction with signatures
+fn read_signed_tx(path: &str) -> Option<String> {
+    let signed_tx = fs::read_to_string(path).ok()?;
+    let tx: Value = serde_json::from_str(&signed_tx).ok()?;
+    let has_signatures = tx["signatures"]
+        .as_array()
+        .is_some_and(|signatures| !signatures.is_empty());
+    has_signatures.then_some(signed_tx)
+}
+
+/// Loads the operator mnemonic from the `LD_TOOL_OPERATOR_MNEMONIC` secret, or from the
+/// age passphrase-encrypted `LD_TOOL_OPERATOR_MNEMONIC_FILE` when built with the
+/// `encrypted-mnemonic` feature
+fn load_mnemonic() -> Result<String> {
+    if let Ok(mnemonic) = env::var("LD_TOOL_OPERATOR_MNEMONIC") {
+        return Ok(mnemonic.trim().to_string());
+    }
+
+    let mnemonic_file = env::var("LD_TOOL_OPERATOR_MNEMONIC_FILE").context(
+        "LD_TOOL_OPERATOR_MNEMONIC or LD_TOOL_OPERATOR_MNEMONIC_FILE environment variable is required",
+    )?;
+    decrypt_mnemonic_file(&mnemonic_file)
+}
+
+#[cfg(feature = "encrypted-mnemonic")]
+fn decrypt_mnemonic_file(mnemonic_file: &str) -> Result<String> {
+    use age::secrecy::SecretString;
+    use std::io::Read;
+
+    let passphrase = env::var("LD_TOOL_OPERATOR_MNEMONIC_PASSPHRASE")
+        .context("LD_TOOL_OPERATOR_MNEMONIC_PASSPHRASE environment variable is required")?;
+
+    let encrypted = fs::read(mnemonic_file)
+        .with_context(|| format!("Failed to read mnemonic file {}", mnemonic_file))?;
+    let decryptor = age::Decryptor::new(&encrypted[..])
+        .map_err(|e| anyhow!("Invalid mnemonic file {}: {}", mnemonic_file, e))?;
+    let identity = age::scrypt::Identity::new(SecretString::from(passphrase));
+
+    let mut mnemonic = String::new();
+    decryptor
+        .decrypt(std::iter::once(&identity as &dyn age::Identity))
+        .map_err(|e| anyhow!("Failed to decrypt mnemonic file {}: {}", mnemonic_file, e))?
+        .read_to_string(&mut mnemonic)?;
+
+    Ok(mnemonic.trim().to_string())
+}
+
+#[cfg(not(feature = "encrypted-mnemonic"))]
+fn decrypt_mnemonic_file(mnemonic_file: &str) -> Result<String> {
+    Err(anyhow!(
+        "Reading the encrypted mnemonic file {} needs a build with the encrypted-mnemonic feature",
+        mnemonic_file
+    ))
+}
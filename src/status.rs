@@ -0,0 +1,329 @@
+use crate::authorization::Authorization;
+use crate::config::Config;
+use crate::processor::{
+    execution_result_name, get_processor_callbacks, get_queue, is_failed_execution,
+    QUEUE_PRIORITIES,
+};
+use crate::program::{PoolTopology, ProgramTopology};
+use crate::wasm::{get_balances, get_ownership, query_wasm_smart};
+use anyhow::{Error, Result};
+use cosmwasm_std::Timestamp;
+use cw_utils::Expiration;
+use serde_json::Value;
+
+pub fn print_authorizations(authorizations: &[Authorization]) {
+    println!("== Authorizations ==");
+    for authorization in authorizations {
+        println!(
+            "{} | state: {} | mode: {} | not before: {} | expiration: {} | max concurrent executions: {} | priority: {}",
+            authorization.label,
+            compact(&authorization.state),
+            compact(&authorization.mode),
+            compact(&authorization.not_before),
+            compact(&authorization.expiration),
+            authorization
+                .max_concurrent_executions
+                .map_or("-".to_string(), |n| n.to_string()),
+            compact(&authorization.priority),
+        );
+    }
+}
+
+pub fn print_processor_queue(
+    config: &Config,
+    auth_contract_address: &str,
+    processor_address: Option<&str>,
+) -> Result<(), Error> {
+    println!("== Processor queue ==");
+    match processor_address {
+        Some(processor_address) => {
+            println!("Processor: {}", processor_address);
+            for priority in QUEUE_PRIORITIES {
+                let queue = get_queue(config, processor_address, priority)?;
+                println!("{} priority: {} pending batch(es)", priority, queue.len());
+                for batch in queue {
+                    println!(
+                        "  execution {} | {} message(s) | retry: {}",
+                        batch["id"],
+                        batch["msgs"].as_array().map_or(0, |msgs| msgs.len()),
+                        compact(&batch["retry"]),
+                    );
+                }
+            }
+        }
+        None => println!("Processor address could not be discovered"),
+    }
+
+    let failed_callbacks: Vec<Value> = get_processor_callbacks(config, auth_contract_address)?
+        .into_iter()
+        .filter(|callback| is_failed_execution(&callback["execution_result"]))
+        .collect();
+    println!("Failed executions: {}", failed_callbacks.len());
+    for callback in failed_callbacks {
+        println!(
+            "  execution {} | {} | {}",
+            callback["execution_id"],
+            callback["label"].as_str().unwrap_or_default(),
+            execution_result_name(&callback["execution_result"]),
+        );
+        println!("    {}", compact(&callback["execution_result"]));
+    }
+
+    Ok(())
+}
+
+pub fn print_ownership(
+    config: &Config,
+    auth_contract_address: &str,
+    accounts: &[String],
+) -> Result<(), Error> {
+    println!("== Ownership ==");
+    for contract_address in std::iter::once(&auth_contract_address.to_string()).chain(accounts) {
+        let ownership = get_ownership(config, contract_address)?;
+        println!("{} | {}", contract_address, ownership_state(&ownership));
+    }
+
+    Ok(())
+}
+
+/// Describes a cw-ownable `ownership {}` response
+pub fn ownership_state(ownership: &Value) -> String {
+    match ownership["pending_owner"].as_str() {
+        Some(pending_owner) => format!(
+            "pending: {} -> {} (expiry: {})",
+            ownership["owner"].as_str().unwrap_or("none"),
+            pending_owner,
+            compact(&ownership["pending_expiry"]),
+        ),
+        None => format!(
+            "accepted: {}",
+            ownership["owner"].as_str().unwrap_or("none")
+        ),
+    }
+}
+
+/// Where the ownership of a program contract stands with respect to the DAO committee
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OwnershipStatus {
+    /// Owned by the committee
+    Accepted,
+    /// Transferred to the committee and waiting for its acceptance
+    Pending,
+    /// Transferred to the committee, but the transfer expired before it was accepted
+    Expired,
+    /// Still owned by the operator without a transfer
+    NotTransferred,
+    /// Owned by, or transferred to, an address that is neither the operator nor the committee
+    Foreign,
+}
+
+impl OwnershipStatus {
+    pub fn name(&self) -> &'static str {
+        match self {
+            OwnershipStatus::Accepted => "accepted",
+            OwnershipStatus::Pending => "pending",
+            OwnershipStatus::Expired => "expired",
+            OwnershipStatus::NotTransferred => "not transferred",
+            OwnershipStatus::Foreign => "foreign",
+        }
+    }
+}
+
+/// Classifies the `ownership {}` response of a contract against the committee and operator,
+/// with the latest block height and time for the expiry of pending transfers
+pub fn classify_ownership(
+    ownership: &Value,
+    dao_address: &str,
+    operator_address: &str,
+    block: (u64, Timestamp),
+) -> OwnershipStatus {
+    let owner = ownership["owner"].as_str();
+    match ownership["pending_owner"].as_str() {
+        _ if owner == Some(dao_address) => OwnershipStatus::Accepted,
+        Some(pending_owner) if pending_owner == dao_address => {
+            let expired = match serde_json::from_value(ownership["pending_expiry"].clone()) {
+                Ok(Expiration::AtHeight(height)) => block.0 >= height,
+                Ok(Expiration::AtTime(time)) => block.1 >= time,
+                _ => false,
+            };
+            if expired {
+                OwnershipStatus::Expired
+            } else {
+                OwnershipStatus::Pending
+            }
+        }
+        Some(_) => OwnershipStatus::Foreign,
+        None if owner == Some(operator_address) => OwnershipStatus::NotTransferred,
+        None => OwnershipStatus::Foreign,
+    }
+}
+
+pub fn print_approved_libraries(config: &Config, accounts: &[String]) -> Result<(), Error> {
+    println!("== Approved libraries ==");
+    for account in accounts {
+        let libraries = query_wasm_smart(config, account, r#"{"list_approved_libraries":{}}"#)?;
+        println!("{} | {}", account, compact(&libraries));
+    }
+
+    Ok(())
+}
+
+pub fn print_balances(config: &Config, topology: &ProgramTopology) -> Result<(), Error> {
+    println!("== Balances ==");
+    if let Some(input_account) = &topology.input_account {
+        print_account_balances(config, "input", input_account)?;
+    }
+    for pool in &topology.pools {
+        print_account_balances(config, "split output", &pool.split_output_account)?;
+        print_account_balances(config, "liquidity output", &pool.liquidity_output_account)?;
+        match &pool.withdrawal_account {
+            Some(withdrawal_account) => {
+                print_account_balances(config, "withdrawal", withdrawal_account)?
+            }
+            None => {
+                if let Some(withdraw_output) = &pool.withdraw_output_address {
+                    print_account_balances(config, "withdraw output", withdraw_output)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn print_account_balances(config: &Config, role: &str, address: &str) -> Result<(), Error> {
+    let balances = get_balances(config, address)?;
+    let balances: Vec<String> = balances.iter().map(|coin| coin.to_string()).collect();
+    println!(
+        "{} ({}) | {}",
+        address,
+        role,
+        if balances.is_empty() {
+            "empty".to_string()
+        } else {
+            balances.join(", ")
+        }
+    );
+
+    Ok(())
+}
+
+pub fn print_lp_positions(config: &Config, pools: &[PoolTopology]) -> Result<(), Error> {
+    println!("== LP positions ==");
+    for pool in pools {
+        let lp_amount = get_lp_token_balance(config, pool)?;
+        println!(
+            "Pool {} ({} / {}) | LP tokens held by {}: {}",
+            pool.pool_address, pool.asset1, pool.asset2, pool.liquidity_output_account, lp_amount
+        );
+
+        if lp_amount != "0" {
+            let share = query_wasm_smart(
+                config,
+                &pool.pool_address,
+                &format!(r#"{{"share":{{"amount":"{}"}}}}"#, lp_amount),
+            )?;
+            for asset in share.as_array().cloned().unwrap_or_default() {
+                println!("  {} {}", asset["amount"], compact(&asset["info"]));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the amount of LP tokens of the pool held by the liquidity output account
+pub fn get_lp_token_balance(config: &Config, pool: &PoolTopology) -> Result<String, Error> {
+    let pair = query_wasm_smart(config, &pool.pool_address, r#"{"pair":{}}"#)?;
+    let liquidity_token = pair["liquidity_token"].as_str().unwrap_or_default();
+
+    if pool.pool_type.get("cw20_lp_token").is_some() {
+        let balance = query_wasm_smart(
+            config,
+            liquidity_token,
+            &format!(
+                r#"{{"balance":{{"address":"{}"}}}}"#,
+                pool.liquidity_output_account
+            ),
+        )?;
+        return Ok(balance["balance"].as_str().unwrap_or("0").to_string());
+    }
+
+    let balances = get_balances(config, &pool.liquidity_output_account)?;
+    Ok(balances
+        .iter()
+        .find(|coin| coin.denom == liquidity_token)
+        .map_or("0".to_string(), |coin| coin.amount.to_string()))
+}
+
+fn compact(value: &Value) -> String {
+    match value {
+        Value::Null => "-".to_string(),
+        Value::String(s) => s.clone(),
+        _ => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const DAO: &str = "dao";
+    const OPERATOR: &str = "operator";
+    const BLOCK: (u64, Timestamp) = (100, Timestamp::from_seconds(1_000));
+
+    fn classify(ownership: Value) -> OwnershipStatus {
+        classify_ownership(&ownership, DAO, OPERATOR, BLOCK)
+    }
+
+    #[test]
+    fn accepted_only_when_owned_by_dao() {
+        assert_eq!(
+            classify(json!({ "owner": DAO, "pending_owner": null, "pending_expiry": null })),
+            OwnershipStatus::Accepted
+        );
+        assert_eq!(
+            classify(json!({ "owner": OPERATOR, "pending_owner": null, "pending_expiry": null })),
+            OwnershipStatus::NotTransferred
+        );
+        assert_eq!(
+            classify(json!({ "owner": "other", "pending_owner": null, "pending_expiry": null })),
+            OwnershipStatus::Foreign
+        );
+    }
+
+    #[test]
+    fn pending_transfers_expire_with_the_block() {
+        let pending = |expiry: Value| {
+            classify(json!({ "owner": OPERATOR, "pending_owner": DAO, "pending_expiry": expiry }))
+        };
+        assert_eq!(pending(Value::Null), OwnershipStatus::Pending);
+        assert_eq!(
+            pending(json!({ "at_height": 101 })),
+            OwnershipStatus::Pending
+        );
+        assert_eq!(
+            pending(json!({ "at_height": 100 })),
+            OwnershipStatus::Expired
+        );
+        assert_eq!(
+            pending(json!({ "at_time": "2000000000000" })),
+            OwnershipStatus::Pending
+        );
+        assert_eq!(
+            pending(json!({ "at_time": "999000000000" })),
+            OwnershipStatus::Expired
+        );
+    }
+
+    #[test]
+    fn transfer_to_another_address_is_foreign() {
+        assert_eq!(
+            classify(
+                json!({ "owner": OPERATOR, "pending_owner": "other", "pending_expiry": null })
+            ),
+            OwnershipStatus::Foreign
+        );
+    }
+}
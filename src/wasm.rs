@@ -1,102 +1,239 @@
 use crate::authorization::{Authorization, AuthorizationsResponse};
-use crate::config::Config;
+use crate::config::{Config, SignerKind};
+use crate::fees::fee_for_gas;
 use crate::node_cmd::{
-    build_query_flags, build_tx_flags, build_wasm_instantiate_flags, run_command,
+    build_query_flags, build_tx_flags, build_wasm_instantiate_flags, poll_command, run_command,
+    run_command_silent, run_tx_command,
 };
+use crate::signer::build_signer;
 use anyhow::{anyhow, Error};
-use serde_json::Value;
+use chrono::{DateTime, Utc};
+use cosmwasm_std::{Coin, Timestamp};
+use serde_json::{json, Value};
 use sha2::{Digest, Sha256};
+use std::env::temp_dir;
+use std::fmt;
+use std::time::Duration;
 use std::{fs::File, io::Read};
 
+const TX_POLL_ATTEMPTS: u32 = 60;
+const TX_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
 pub fn execute_wasm_contract(
     contract_address: &str,
     msg: &str,
     config: &Config,
 ) -> Result<(), Error> {
+    // Wait for the transaction to be included so the next one uses the right sequence
+    execute_wasm_contract_with_tx(contract_address, msg, config)?;
+    Ok(())
+}
+
+/// Executes the contract and returns the indexed transaction, including its events
+pub fn execute_wasm_contract_with_tx(
+    contract_address: &str,
+    msg: &str,
+    config: &Config,
+) -> Result<Value, Error> {
+    // Other signers need the transaction to be generated and signed separately
+    if config.signer != SignerKind::Keyring {
+        let (msg, gas) = generate_wasm_execute_msg(contract_address, msg, config)?;
+        return broadcast_msgs(&[msg], gas, config);
+    }
+
     let flags = build_tx_flags(config);
     let cmd = format!(
         "{} tx wasm execute {} '{}' {}",
         config.neutron_binary, contract_address, msg, flags
     );
 
-    run_command(&cmd)?;
-    Ok(())
+    let output = run_tx_command(&cmd, config)?;
+    query_broadcasted_tx(&output, config)
 }
 
-pub fn instantiate_wasm_contract(
-    code_id: u64,
+/// Waits for the transaction from the output of a broadcast command to be indexed and returns it
+fn query_broadcasted_tx(broadcast_output: &str, config: &Config) -> Result<Value, Error> {
+    let tx_output: Value = serde_json::from_str(broadcast_output)?;
+    let tx_hash = tx_output["txhash"]
+        .as_str()
+        .ok_or_else(|| anyhow!("Failed to extract txhash"))?;
+
+    // Transaction rejected before entering the mempool
+    if tx_output["code"].as_u64().unwrap_or(0) != 0 {
+        return Err(anyhow!(
+            "Transaction {} failed: {}",
+            tx_hash,
+            tx_output["raw_log"]
+        ));
+    }
+
+    // Query the transaction by tx hash until it is indexed
+    let query_cmd = format!(
+        "{} q tx {} --node {} --output json",
+        config.neutron_binary, tx_hash, config.neutron_rpc
+    );
+    let tx_response = poll_command(&query_cmd, TX_POLL_ATTEMPTS, TX_POLL_INTERVAL)?;
+    let tx_data: Value = serde_json::from_str(&tx_response)?;
+
+    // Transaction included in a block but failed during execution, its fee is still paid
+    if tx_data["code"].as_u64().unwrap_or(0) != 0 {
+        return Err(TxFailed {
+            tx_hash: tx_hash.to_string(),
+            gas_used: tx_gas_used(&tx_data),
+            raw_log: tx_data["raw_log"].to_string(),
+        }
+        .into());
+    }
+
+    Ok(tx_data)
+}
+
+/// Transaction that was included in a block but failed, with the gas it used
+#[derive(Debug)]
+pub struct TxFailed {
+    pub tx_hash: String,
+    pub gas_used: u64,
+    pub raw_log: String,
+}
+
+impl fmt::Display for TxFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Transaction {} failed: {}", self.tx_hash, self.raw_log)
+    }
+}
+
+impl std::error::Error for TxFailed {}
+
+/// Gas used by an indexed transaction
+pub fn tx_gas_used(tx_data: &Value) -> u64 {
+    tx_data["gas_used"]
+        .as_str()
+        .and_then(|gas| gas.parse().ok())
+        .unwrap_or_default()
+}
+
+/// Returns the values of all event attributes with the given key
+pub fn find_event_attributes(tx_data: &Value, key: &str) -> Vec<String> {
+    tx_data["events"]
+        .as_array()
+        .map(|events| {
+            events
+                .iter()
+                .flat_map(|event| event["attributes"].as_array())
+                .flatten()
+                .filter(|attr| attr["key"] == key)
+                .filter_map(|attr| attr["value"].as_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Generates an unsigned execute message without broadcasting it.
+/// Returns the message and the gas estimated for it on the current chain state.
+pub fn generate_wasm_execute_msg(
+    contract_address: &str,
     msg: &str,
     config: &Config,
-    label: &str,
-) -> Result<String, Error> {
-    instantiate_wasm_contract_internal(code_id, msg, config, label, None)
+) -> Result<(Value, u64), Error> {
+    let flags = build_tx_flags(config);
+    let cmd = format!(
+        "{} tx wasm execute {} '{}' {} --generate-only",
+        config.neutron_binary, contract_address, msg, flags
+    );
+
+    parse_generated_msg(&run_tx_command(&cmd, config)?)
 }
 
-pub fn instantiate2_wasm_contract(
+/// Generates an unsigned instantiate2 message with a hex-encoded salt, so the address of the
+/// contract can be predicted with `instantiate2_address`
+pub fn generate_wasm_instantiate2_msg(
     code_id: u64,
     msg: &str,
     config: &Config,
     label: &str,
     salt: &str,
-) -> Result<String, Error> {
-    instantiate_wasm_contract_internal(code_id, msg, config, label, Some(salt))
+) -> Result<(Value, u64), Error> {
+    let flags = build_tx_flags(config);
+    let init_flags = build_wasm_instantiate_flags(config, label);
+    let cmd = format!(
+        "{} tx wasm instantiate2 {} '{}' {} --hex {} {} --generate-only",
+        config.neutron_binary, code_id, msg, salt, init_flags, flags
+    );
+
+    parse_generated_msg(&run_tx_command(&cmd, config)?)
 }
 
-// Internal function that handles both instantiations
-fn instantiate_wasm_contract_internal(
-    code_id: u64,
-    msg: &str,
+/// Generates an unsigned bank send from the operator without broadcasting it
+pub fn generate_bank_send_msg(
+    to_address: &str,
+    amount: &str,
     config: &Config,
-    label: &str,
-    salt: Option<&str>,
-) -> Result<String, Error> {
+) -> Result<(Value, u64), Error> {
     let flags = build_tx_flags(config);
-    let init_flags = build_wasm_instantiate_flags(config, label);
+    let cmd = format!(
+        "{} tx bank send {} {} {} {} --generate-only",
+        config.neutron_binary, config.tool_operator_address, to_address, amount, flags
+    );
 
-    let cmd = match salt {
-        Some(s) => format!(
-            "{} tx wasm instantiate2 {} '{}' {} {} {}",
-            config.neutron_binary, code_id, msg, s, init_flags, flags
-        ),
-        None => format!(
-            "{} tx wasm instantiate {} '{}' {} {}",
-            config.neutron_binary, code_id, msg, init_flags, flags
-        ),
-    };
+    parse_generated_msg(&run_tx_command(&cmd, config)?)
+}
 
-    let output = run_command(&cmd)?;
+fn parse_generated_msg(generate_output: &str) -> Result<(Value, u64), Error> {
+    let tx: Value = serde_json::from_str(generate_output)?;
+    let msg = tx["body"]["messages"][0].clone();
+    if msg.is_null() {
+        return Err(anyhow!("No message found in the generated transaction"));
+    }
 
-    let tx_output: Value = serde_json::from_str(&output)?;
-    let tx_hash = tx_output["txhash"]
+    let gas = tx["auth_info"]["fee"]["gas_limit"]
         .as_str()
-        .ok_or_else(|| anyhow!("Failed to extract txhash"))?;
+        .and_then(|gas| gas.parse().ok())
+        .ok_or_else(|| anyhow!("Failed to extract the estimated gas"))?;
 
-    // Query the transaction by tx hash
-    let query_cmd = format!(
-        "{} q tx {} --node {} --output json",
-        config.neutron_binary, tx_hash, config.neutron_rpc
-    );
-    let tx_response = run_command(&query_cmd)?;
-
-    let tx_data: Value = serde_json::from_str(&tx_response)?;
+    Ok((msg, gas))
+}
 
-    // Find contract address in events
-    let contract_address = tx_data["events"]
-        .as_array()
-        .ok_or_else(|| anyhow!("Failed to parse transaction events"))?
-        .iter()
-        .flat_map(|event| event["attributes"].as_array())
-        .flatten()
-        .find_map(|attr| {
-            if attr["key"] == "_contract_address" {
-                attr["value"].as_str().map(String::from)
-            } else {
-                None
+/// Signs and broadcasts a single transaction with all the messages and waits for it to be included
+pub fn broadcast_msgs(msgs: &[Value], gas: u64, config: &Config) -> Result<Value, Error> {
+    let unsigned_tx = json!({
+        "body": {
+            "messages": msgs,
+            "memo": "",
+            "timeout_height": "0",
+            "extension_options": [],
+            "non_critical_extension_options": []
+        },
+        "auth_info": {
+            "signer_infos": [],
+            "fee": {
+                "amount": [fee_for_gas(gas, &config.gas_price)?],
+                "gas_limit": gas.to_string(),
+                "payer": config.fee_payer.as_ref().map_or("", |payer| payer.as_str()),
+                "granter": config.fee_granter.as_ref().map_or("", |granter| granter.as_str())
             }
-        })
-        .ok_or_else(|| anyhow!("Contract address not found in transaction events"))?;
+        },
+        "signatures": []
+    });
+
+    let tx_file = temp_dir().join(format!(
+        "ld_tool_tx_{}.json",
+        Utc::now().timestamp_nanos_opt().unwrap_or_default()
+    ));
+    let tx_path = tx_file.display().to_string();
+    std::fs::write(&tx_file, serde_json::to_string(&unsigned_tx)?)?;
 
-    Ok(contract_address)
+    let signed_tx = build_signer(config).and_then(|signer| signer.sign(&tx_file));
+    std::fs::remove_file(&tx_file)?;
+    std::fs::write(&tx_file, signed_tx?)?;
+
+    let broadcast_cmd = format!(
+        "{} tx broadcast {} --node {} --output=json",
+        config.neutron_binary, tx_path, config.neutron_rpc
+    );
+    let output = run_command(&broadcast_cmd);
+    std::fs::remove_file(&tx_file)?;
+
+    query_broadcasted_tx(&output?, config)
 }
 
 pub fn get_code_hash(config: &Config, code_id: u64) -> Result<String, Error> {
@@ -124,19 +261,160 @@ pub fn get_code_hash(config: &Config, code_id: u64) -> Result<String, Error> {
     Ok(hash_hex)
 }
 
+/// Returns the bech32 prefix of the chain's account addresses
+pub fn get_bech32_prefix(config: &Config) -> Result<String, Error> {
+    let flags = build_query_flags(config);
+    let cmd = format!("{} q auth bech32-prefix {}", config.neutron_binary, flags);
+
+    let output = run_command(&cmd)?;
+    let response: Value = serde_json::from_str(&output)?;
+    response["bech32_prefix"]
+        .as_str()
+        .map(String::from)
+        .ok_or_else(|| anyhow!("Failed to extract the bech32 prefix"))
+}
+
+/// Returns the height and time of the latest block, which expirations are checked against
+pub fn get_latest_block(config: &Config) -> Result<(u64, Timestamp), Error> {
+    let cmd = format!(
+        "{} status --node {}",
+        config.neutron_binary, config.neutron_rpc
+    );
+
+    let output = run_command(&cmd)?;
+    let response: Value = serde_json::from_str(&output)?;
+    // older node versions report the sync info in Go field case
+    let sync_info = if response["sync_info"].is_null() {
+        &response["SyncInfo"]
+    } else {
+        &response["sync_info"]
+    };
+
+    let height = sync_info["latest_block_height"]
+        .as_str()
+        .and_then(|height| height.parse().ok())
+        .ok_or_else(|| anyhow!("Failed to extract the latest block height"))?;
+    let time = sync_info["latest_block_time"]
+        .as_str()
+        .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+        .and_then(|time| time.timestamp_nanos_opt())
+        .ok_or_else(|| anyhow!("Failed to extract the latest block time"))?;
+
+    Ok((height, Timestamp::from_nanos(time as u64)))
+}
+
+/// Returns the address of the operator key in the keyring
+pub fn get_key_address(config: &Config) -> Result<String, Error> {
+    let cmd = format!(
+        "{} keys show {} -a --keyring-backend={} --home {}",
+        config.neutron_binary, config.tool_operator_moniker, config.keyring_backend, config.home
+    );
+
+    Ok(run_tx_command(&cmd, config)?.trim().to_string())
+}
+
+/// Returns the feegrant allowance of the grantee from the granter, if there is one
+pub fn get_fee_allowance(
+    config: &Config,
+    granter: &str,
+    grantee: &str,
+) -> Result<Option<Value>, Error> {
+    let flags = build_query_flags(config);
+    let cmd = format!(
+        "{} q feegrant grant {} {} {}",
+        config.neutron_binary, granter, grantee, flags
+    );
+
+    match run_command_silent(&cmd) {
+        Ok(output) => {
+            let response: Value = serde_json::from_str(&output)?;
+            Ok(Some(response["allowance"]["allowance"].clone()))
+        }
+        Err(e) if e.to_string().contains("not found") => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Checks whether a contract is instantiated at the address
+pub fn contract_exists(config: &Config, contract_address: &str) -> Result<bool, Error> {
+    let flags = build_query_flags(config);
+    let cmd = format!(
+        "{} q wasm contract {} {}",
+        config.neutron_binary, contract_address, flags
+    );
+
+    match run_command_silent(&cmd) {
+        Ok(_) => Ok(true),
+        Err(e) if e.to_string().contains("not found") => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// Returns the code id the contract was instantiated from
+pub fn get_contract_code_id(config: &Config, contract_address: &str) -> Result<u64, Error> {
+    let flags = build_query_flags(config);
+    let cmd = format!(
+        "{} q wasm contract {} {}",
+        config.neutron_binary, contract_address, flags
+    );
+
+    let response: Value = serde_json::from_str(&run_command(&cmd)?)?;
+    response["contract_info"]["code_id"]
+        .as_str()
+        .and_then(|code_id| code_id.parse().ok())
+        .ok_or_else(|| anyhow!("No code id found for contract {}", contract_address))
+}
+
+pub fn query_wasm_smart(
+    config: &Config,
+    contract_address: &str,
+    query_msg: &str,
+) -> Result<Value, Error> {
+    let flags = build_query_flags(config);
+    let cmd = format!(
+        "{} q wasm contract-state smart {} '{}' {}",
+        config.neutron_binary, contract_address, query_msg, flags
+    );
+
+    let output = run_command(&cmd)?;
+    let response: Value = serde_json::from_str(&output)?;
+
+    Ok(response["data"].clone())
+}
+
+pub fn get_balances(config: &Config, address: &str) -> Result<Vec<Coin>, Error> {
+    let flags = build_query_flags(config);
+    let cmd = format!(
+        "{} q bank balances {} {}",
+        config.neutron_binary, address, flags
+    );
+
+    let output = run_command(&cmd)?;
+    let response: Value = serde_json::from_str(&output)?;
+    let balances: Vec<Coin> = serde_json::from_value(response["balances"].clone())?;
+
+    Ok(balances)
+}
+
+pub fn get_ownership(config: &Config, contract_address: &str) -> Result<Value, Error> {
+    query_wasm_smart(config, contract_address, r#"{"ownership":{}}"#)
+}
+
+pub fn get_library_config(config: &Config, library_address: &str) -> Result<Value, Error> {
+    query_wasm_smart(config, library_address, r#"{"get_library_config":{}}"#)
+}
+
 pub fn get_authorizations(
     config: &Config,
     auth_contract_address: &str,
 ) -> Result<Vec<Authorization>, Error> {
     let flags = build_query_flags(config);
-    let query_msg = format!(
-        r#"{{
-            "authorizations": {{
+    let query_msg = r#"{
+            "authorizations": {
                 "start_after": null,
                 "limit": 100
-            }}
-        }}"#
-    );
+            }
+        }"#;
 
     let cmd = format!(
         "{} q wasm contract-state smart {} '{}' {}",